@@ -63,6 +63,7 @@ fn setup(
             visible: true,
             colour: Color::srgb(1.0, 1.0, 0.0),
             width: 0.0,
+            ..default()
         },
         OutlineAlphaMask {
             texture: Some(alpha_mask_handle),
@@ -1,12 +1,20 @@
 use std::f32::consts::PI;
 
-use bevy::{prelude::*, world_serialization::WorldInstance};
+use bevy::{
+    animation::{AnimatedBy, AnimationTargetId},
+    prelude::*,
+    world_serialization::WorldInstance,
+};
 use bevy_mod_outline::{
     AsyncWorldInheritOutline, AutoGenerateOutlineNormalsPlugin, OutlinePlugin, OutlineVolume,
+    OutlineVolumeColour, OutlineVolumeWidth,
 };
 
 #[derive(Resource)]
-struct Fox(Handle<AnimationClip>);
+struct Fox {
+    animation: Handle<AnimationClip>,
+    outline_animation: Handle<AnimationClip>,
+}
 
 fn main() {
     App::new()
@@ -26,9 +34,37 @@ fn setup(
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut clips: ResMut<Assets<AnimationClip>>,
 ) {
-    // Insert a resource with the current animation
-    commands.insert_resource(Fox(asset_server.load("Fox.glb#Animation0")));
+    // Keyframe the outline to pulse alongside the skeletal animation
+    let outline_target = AnimationTargetId::from_name(&Name::new("Outline"));
+    let mut outline_clip = AnimationClip::default();
+    outline_clip.add_curve_to_target(
+        outline_target,
+        AnimatableCurve::new(
+            OutlineVolumeWidth,
+            AnimatableKeyframeCurve::new([(0.0, 3.0), (0.35, 6.0), (0.7, 3.0)])
+                .expect("keyframes should be valid"),
+        ),
+    );
+    outline_clip.add_curve_to_target(
+        outline_target,
+        AnimatableCurve::new(
+            OutlineVolumeColour::default(),
+            AnimatableKeyframeCurve::new([
+                (0.0, Srgba::RED),
+                (0.35, Srgba::rgb(1.0, 0.5, 0.0)),
+                (0.7, Srgba::RED),
+            ])
+            .expect("keyframes should be valid"),
+        ),
+    );
+
+    // Insert a resource with the current animations
+    commands.insert_resource(Fox {
+        animation: asset_server.load("Fox.glb#Animation0"),
+        outline_animation: clips.add(outline_clip),
+    });
 
     // Camera
     commands.spawn((
@@ -64,15 +100,17 @@ fn setup(
             visible: true,
             width: 3.0,
             colour: Color::srgb(1.0, 0.0, 0.0),
+            ..default()
         },
         AsyncWorldInheritOutline::default(),
+        outline_target,
     ));
 }
 
 // Once the scene is loaded, start the animation
 fn setup_scene_once_loaded(
     mut commands: Commands,
-    scene_query: Query<&WorldInstance>,
+    scene_query: Query<(Entity, &WorldInstance)>,
     scene_manager: Res<WorldInstanceSpawner>,
     mut player_query: Query<(Entity, &mut AnimationPlayer)>,
     animation: Res<Fox>,
@@ -80,15 +118,22 @@ fn setup_scene_once_loaded(
     mut graphs: ResMut<Assets<AnimationGraph>>,
 ) {
     if !*done {
-        if let (Ok(scene), Ok((entity, mut player))) =
+        if let (Ok((root, scene)), Ok((entity, mut player))) =
             (scene_query.single(), player_query.single_mut())
         {
             if scene_manager.instance_is_ready(**scene) {
-                let (graph, animation) = AnimationGraph::from_clip(animation.0.clone());
+                let (graph, animations) = AnimationGraph::from_clips([
+                    animation.animation.clone(),
+                    animation.outline_animation.clone(),
+                ]);
                 commands
                     .entity(entity)
                     .insert(AnimationGraphHandle(graphs.add(graph)));
-                player.play(animation).repeat();
+                for node in animations {
+                    player.play(node).repeat();
+                }
+                // The outline is on the scene root, outside the player's hierarchy
+                commands.entity(root).insert(AnimatedBy(entity));
                 *done = true;
             }
         }
@@ -41,6 +41,7 @@ fn setup(
             visible: true,
             colour: Color::WHITE,
             width: 5.0,
+            ..default()
         },
         Pulses(0.0),
     ));
@@ -62,6 +63,7 @@ fn setup(
                     visible: true,
                     colour: Color::WHITE,
                     width: 5.0,
+                    intensity: 8.0,
                 },
                 Pulses(PI),
             ));
@@ -73,6 +75,8 @@ fn setup(
         Transform::from_xyz(0.0, 3.0, 4.0).looking_at(Vec3::ZERO, Vec3::Y),
         Hdr,
         Msaa::Sample4,
+        // Draw outlines before bloom so that intense outlines glow
+        OutlinePassPlacement::PreTonemap,
         Bloom {
             intensity: 1.0,
             low_frequency_boost: 0.5,
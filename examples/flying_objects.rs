@@ -88,6 +88,7 @@ fn spawn_objects(
                 } else {
                     Color::srgb(1.0, 0.0, 0.0)
                 },
+                ..default()
             },
             FlyingObject,
         ));
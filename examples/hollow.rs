@@ -52,6 +52,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             visible: true,
             width: 0.0,
             colour: Color::srgb(0.0, 0.0, 1.0),
+            ..default()
         },
         PropagateOutline,
     ));
@@ -48,6 +48,7 @@ fn setup(asset_server: Res<AssetServer>, mut commands: Commands) {
             visible: true,
             width: 3.0,
             colour: Color::srgb(1.0, 0.0, 0.0),
+            ..default()
         },
     ));
     commands.spawn(DirectionalLight {
@@ -35,6 +35,7 @@ fn setup(
             visible: true,
             colour: Color::srgb(0.0, 1.0, 0.0),
             width: 8.0,
+            ..default()
         },
     ));
 
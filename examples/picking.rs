@@ -1,13 +1,14 @@
 use bevy::prelude::*;
 use bevy_mod_outline::*;
 
-/// Tag to track if an object is selected or not
-#[derive(Component)]
-struct Selected;
-
 fn main() {
     App::new()
-        .add_plugins((DefaultPlugins, MeshPickingPlugin, OutlinePlugin::JUMP_FLOOD))
+        .add_plugins((
+            DefaultPlugins,
+            MeshPickingPlugin,
+            OutlinePlugin::JUMP_FLOOD,
+            OutlinePickingPlugin,
+        ))
         .add_systems(Startup, setup)
         .add_systems(Update, rotate_selected)
         .run();
@@ -17,7 +18,6 @@ fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut window: Query<Entity, With<Window>>,
 ) {
     let shapes = [
         meshes.add(Cuboid::default()),
@@ -39,27 +39,26 @@ fn setup(
         Vec3::new(DISTANCE, -DISTANCE, DISTANCE),
     ];
 
-    // Spawn shapes with outline and picking observer
+    // Spawn shapes with hover and selection outlines
     for i in 0..shapes.len() {
-        commands
-            .spawn((
-                Mesh3d(shapes[i].clone()),
-                MeshMaterial3d(material[i].clone()),
-                Transform::from_translation(positions[i]),
-                OutlineVolume {
-                    width: 5.0f32,
-                    ..default()
-                },
-                Pickable::default(),
-            ))
-            .observe(on_click);
-    }
-
-    // Add an observer to the window to respond to clicks that don't hit a mesh
-    if let Ok(entity) = window.single_mut() {
-        if let Ok(mut window) = commands.get_entity(entity) {
-            window.observe(on_click);
-        }
+        commands.spawn((
+            Mesh3d(shapes[i].clone()),
+            MeshMaterial3d(material[i].clone()),
+            Transform::from_translation(positions[i]),
+            HoverOutline(OutlineVolume {
+                visible: true,
+                width: 3.0,
+                colour: Color::srgb(0.5, 0.5, 0.5),
+                ..default()
+            }),
+            SelectOutline(OutlineVolume {
+                visible: true,
+                width: 5.0,
+                colour: Color::WHITE,
+                ..default()
+            }),
+            Pickable::default(),
+        ));
     }
 
     // Add ground
@@ -87,56 +86,8 @@ fn setup(
     ));
 }
 
-/// Observer system that manages what pickable objects are selected
-fn on_click(
-    mut event: On<Pointer<Click>>,
-    mut commands: Commands,
-    mut query: Query<(Entity, &mut OutlineVolume, Option<&Selected>)>,
-    keys: Res<ButtonInput<KeyCode>>,
-) {
-    // Disable propagation to prevent observers other than the first in the queue from responding
-    event.propagate(false);
-
-    /// Helper function to deselect every selected entity
-    fn deselect_all(
-        commands: &mut Commands,
-        query: &mut Query<(Entity, &mut OutlineVolume, Option<&Selected>)>,
-    ) {
-        for (entity, mut outline, selected) in query.iter_mut() {
-            if selected.is_some() {
-                if let Ok(mut entity) = commands.get_entity(entity) {
-                    entity.remove::<Selected>();
-                    outline.visible = false;
-                }
-            }
-        }
-    }
-
-    // Check if the user wants to select multiple objects
-    let multi_select = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
-
-    // Deselect everything if the user does not want to select multiple objects
-    if !multi_select {
-        deselect_all(&mut commands, &mut query);
-    }
-
-    // Select a mesh
-    if let Ok((entity, mut outline, selected)) = query.get_mut(event.entity) {
-        if let Ok(mut entity) = commands.get_entity(entity) {
-            // When selecting multiple objects, allow clicking a selected object to deselect it.
-            if multi_select && selected.is_some() {
-                entity.remove::<Selected>();
-                outline.visible = false;
-            } else {
-                entity.insert(Selected);
-                outline.visible = true;
-            }
-        }
-    }
-}
-
 /// Rotate selected meshes
-fn rotate_selected(mut query: Query<&mut Transform, With<Selected>>) {
+fn rotate_selected(mut query: Query<&mut Transform, With<OutlineSelected>>) {
     const SPEED: f32 = 0.1;
     for mut transform in query.iter_mut() {
         transform.rotate_x(SPEED);
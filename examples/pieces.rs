@@ -32,6 +32,7 @@ fn setup(
                 visible: true,
                 colour: Color::WHITE,
                 width: 10.0,
+                ..default()
             },
             OutlineStencil {
                 offset: 5.0,
@@ -51,6 +51,7 @@ fn setup(
             visible: true,
             colour: Color::WHITE,
             width: 10.0,
+            ..default()
         },
         RenderLayers::layer(OBJECT_LAYER_ID),
         OutlineRenderLayers(RenderLayers::layer(OUTLINE_LAYER_ID)),
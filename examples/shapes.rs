@@ -38,6 +38,7 @@ fn setup(
             visible: true,
             colour: Color::srgb(0.0, 1.0, 0.0),
             width: 25.0,
+            ..default()
         },
         OutlineMode::FloodFlat,
         Wobbles,
@@ -63,6 +64,7 @@ fn setup(
             visible: true,
             colour: Color::srgba(1.0, 0.0, 1.0, 0.3),
             width: 15.0,
+            ..default()
         },
         Orbits,
     ));
@@ -127,6 +127,7 @@ fn setup(
             visible: true,
             width: 25.0,
             colour: Color::srgb(1.0, 1.0, 0.0),
+            ..default()
         },
         TheOutline,
         Bounce,
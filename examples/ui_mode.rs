@@ -73,6 +73,7 @@ enum DrawMode {
     ExtrudeFlat,
     ExtrudeReal,
     FloodFlat,
+    FloodReal,
 }
 
 #[derive(Copy, Clone, States, Debug, Default, PartialEq, Eq, Hash)]
@@ -165,6 +166,7 @@ fn setup(
             visible: true,
             width: 25.0,
             colour: Color::srgb(1.0, 1.0, 0.0),
+            ..default()
         },
         Wireframe,
         TheObject,
@@ -236,6 +238,7 @@ fn mode_menu() -> impl Scene {
             menu_item(DrawMode::ExtrudeFlat),
             menu_item(DrawMode::ExtrudeReal),
             menu_item(DrawMode::FloodFlat),
+            menu_item(DrawMode::FloodReal),
         ],
     )
 }
@@ -300,6 +303,7 @@ fn apply_mode(
         DrawMode::ExtrudeFlat => OutlineMode::ExtrudeFlat,
         DrawMode::ExtrudeReal => OutlineMode::ExtrudeReal,
         DrawMode::FloodFlat => OutlineMode::FloodFlat,
+        DrawMode::FloodReal => OutlineMode::FloodReal,
     };
     commands.entity(*object).insert(mode);
 }
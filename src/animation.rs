@@ -0,0 +1,309 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+use bevy::animation::{
+    animatable::Animatable,
+    animation_curves::{AnimatableProperty, EvaluatorId},
+    AnimationEntityMut, AnimationEvaluationError,
+};
+use bevy::ecs::component::Mutable;
+use bevy::prelude::*;
+
+use crate::{OutlinePlaneDepth, OutlineStencil, OutlineVolume};
+
+/// Returns the component `C` of an animated entity.
+fn animated_component<'a, C: Component<Mutability = Mutable>>(
+    entity: &'a mut AnimationEntityMut,
+) -> Result<&'a mut C, AnimationEvaluationError> {
+    entity
+        .get_mut::<C>()
+        .map(Mut::into_inner)
+        .ok_or_else(|| AnimationEvaluationError::ComponentNotPresent(TypeId::of::<C>()))
+}
+
+macro_rules! impl_animatable_property {
+    ($(#[$attr:meta])* $name:ident, $component:ty, $field:ident: $property:ty) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, Default)]
+        pub struct $name;
+
+        impl AnimatableProperty for $name {
+            type Property = $property;
+
+            fn get_mut<'a>(
+                &self,
+                entity: &'a mut AnimationEntityMut,
+            ) -> Result<&'a mut $property, AnimationEvaluationError> {
+                Ok(&mut animated_component::<$component>(entity)?.$field)
+            }
+
+            fn evaluator_id(&self) -> EvaluatorId<'_> {
+                EvaluatorId::Type(TypeId::of::<Self>())
+            }
+        }
+    };
+}
+
+impl_animatable_property!(
+    /// An [`AnimatableProperty`] for animating [`OutlineVolume::width`].
+    OutlineVolumeWidth,
+    OutlineVolume,
+    width: f32
+);
+
+impl_animatable_property!(
+    /// An [`AnimatableProperty`] for animating [`OutlineStencil::offset`].
+    OutlineStencilOffset,
+    OutlineStencil,
+    offset: f32
+);
+
+impl_animatable_property!(
+    /// An [`AnimatableProperty`] for animating
+    /// [`OutlinePlaneDepth::model_plane_origin`].
+    OutlinePlaneDepthOrigin,
+    OutlinePlaneDepth,
+    model_plane_origin: Vec3
+);
+
+impl_animatable_property!(
+    /// An [`AnimatableProperty`] for animating
+    /// [`OutlinePlaneDepth::model_plane_offset`].
+    OutlinePlaneDepthOffset,
+    OutlinePlaneDepth,
+    model_plane_offset: Vec3
+);
+
+/// A colour space in which an [`OutlineVolumeColour`] may be animated.
+pub trait AnimatableColourSpace: Animatable + Copy + Default {
+    /// Returns the colour in this colour space, first converting it if it is
+    /// stored in another.
+    fn colour_mut(colour: &mut Color) -> &mut Self;
+}
+
+macro_rules! impl_animatable_colour_space {
+    ($space:ident) => {
+        impl AnimatableColourSpace for $space {
+            fn colour_mut(colour: &mut Color) -> &mut Self {
+                if !matches!(colour, Color::$space(_)) {
+                    *colour = Color::$space($space::from(*colour));
+                }
+                match colour {
+                    Color::$space(colour) => colour,
+                    _ => unreachable!(),
+                }
+            }
+        }
+    };
+}
+
+impl_animatable_colour_space!(Srgba);
+impl_animatable_colour_space!(LinearRgba);
+impl_animatable_colour_space!(Laba);
+impl_animatable_colour_space!(Oklaba);
+impl_animatable_colour_space!(Xyza);
+
+/// An [`AnimatableProperty`] for animating [`OutlineVolume::colour`] in the
+/// colour space `C`.
+///
+/// A colour given in another space is converted into `C` when first animated
+/// and is stored in `C` from then on. To keep the space a colour was given in,
+/// match `C` to it, e.g. use `OutlineVolumeColour::<Srgba>` to animate a
+/// colour created with [`Color::srgb`].
+#[derive(Debug)]
+pub struct OutlineVolumeColour<C = Srgba>(PhantomData<C>);
+
+impl<C> Clone for OutlineVolumeColour<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for OutlineVolumeColour<C> {}
+
+impl<C> Default for OutlineVolumeColour<C> {
+    fn default() -> Self {
+        OutlineVolumeColour(PhantomData)
+    }
+}
+
+impl<C: AnimatableColourSpace> AnimatableProperty for OutlineVolumeColour<C> {
+    type Property = C;
+
+    fn get_mut<'a>(
+        &self,
+        entity: &'a mut AnimationEntityMut,
+    ) -> Result<&'a mut C, AnimationEvaluationError> {
+        Ok(C::colour_mut(
+            &mut animated_component::<OutlineVolume>(entity)?.colour,
+        ))
+    }
+
+    fn evaluator_id(&self) -> EvaluatorId<'_> {
+        EvaluatorId::Type(TypeId::of::<Self>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::animation::{
+        animation_curves::{AnimatableCurve, AnimatableKeyframeCurve},
+        AnimatedBy, AnimationTargetId,
+    };
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    /// Plays a clip animating the property from `start` to `end` over one
+    /// second on an entity with the given components, returning the entity
+    /// and the time sampled.
+    fn animate<P>(
+        property: P,
+        start: P::Property,
+        end: P::Property,
+        components: impl Bundle,
+    ) -> (App, Entity, f32)
+    where
+        P: AnimatableProperty + Clone,
+        P::Property: Clone,
+    {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), AnimationPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                250,
+            )));
+
+        let target_id = AnimationTargetId::from_name(&Name::new("outline"));
+        let mut clip = AnimationClip::default();
+        clip.add_curve_to_target(
+            target_id,
+            AnimatableCurve::new(
+                property,
+                AnimatableKeyframeCurve::new([(0.0, start), (1.0, end)]).unwrap(),
+            ),
+        );
+        let clip = app
+            .world_mut()
+            .resource_mut::<Assets<AnimationClip>>()
+            .add(clip);
+        let (graph, node) = AnimationGraph::from_clip(clip);
+        let graph = app
+            .world_mut()
+            .resource_mut::<Assets<AnimationGraph>>()
+            .add(graph);
+        let mut player = AnimationPlayer::default();
+        player.play(node);
+        let player = app
+            .world_mut()
+            .spawn((player, AnimationGraphHandle(graph)))
+            .id();
+        let entity = app
+            .world_mut()
+            .spawn((components, target_id, AnimatedBy(player)))
+            .id();
+
+        app.update();
+        app.update();
+        let seek_time = app
+            .world()
+            .get::<AnimationPlayer>(player)
+            .unwrap()
+            .animation(node)
+            .unwrap()
+            .seek_time();
+        assert!(seek_time > 0.0 && seek_time < 1.0);
+        (app, entity, seek_time)
+    }
+
+    fn volume(colour: Color) -> OutlineVolume {
+        OutlineVolume {
+            visible: true,
+            width: 1.0,
+            colour,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_animate_volume_width() {
+        let (app, entity, t) = animate(OutlineVolumeWidth, 2.0, 6.0, volume(Color::WHITE));
+        let volume = app.world().get::<OutlineVolume>(entity).unwrap();
+        assert!((volume.width - f32::interpolate(&2.0, &6.0, t)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_animate_volume_colour() {
+        let start = Srgba::RED;
+        let end = Srgba::BLUE;
+        let (app, entity, t) = animate(
+            OutlineVolumeColour::default(),
+            start,
+            end,
+            volume(start.into()),
+        );
+        // The colour remains in the space it was given in
+        let volume = app.world().get::<OutlineVolume>(entity).unwrap();
+        let Color::Srgba(colour) = volume.colour else {
+            panic!("Expected an sRGB colour, got {:?}", volume.colour);
+        };
+        let expected = Srgba::interpolate(&start, &end, t);
+        assert!(colour.to_vec4().abs_diff_eq(expected.to_vec4(), 1e-5));
+    }
+
+    #[test]
+    fn test_animate_volume_colour_converts_space() {
+        let start = LinearRgba::GREEN;
+        let end = LinearRgba::BLUE;
+        let (app, entity, t) = animate(
+            OutlineVolumeColour::default(),
+            start,
+            end,
+            volume(Color::srgb(1.0, 0.0, 0.0)),
+        );
+        // The colour is converted into the animated space
+        let volume = app.world().get::<OutlineVolume>(entity).unwrap();
+        let Color::LinearRgba(colour) = volume.colour else {
+            panic!("Expected a linear colour, got {:?}", volume.colour);
+        };
+        let expected = LinearRgba::interpolate(&start, &end, t);
+        assert!(colour.to_vec4().abs_diff_eq(expected.to_vec4(), 1e-5));
+    }
+
+    #[test]
+    fn test_animate_stencil_offset() {
+        let (app, entity, t) = animate(OutlineStencilOffset, 0.0, 4.0, OutlineStencil::default());
+        let stencil = app.world().get::<OutlineStencil>(entity).unwrap();
+        assert!((stencil.offset - f32::interpolate(&0.0, &4.0, t)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_animate_plane_depth() {
+        let start = Vec3::ZERO;
+        let end = Vec3::new(1.0, 2.0, 3.0);
+        let (app, entity, t) = animate(
+            OutlinePlaneDepthOrigin,
+            start,
+            end,
+            OutlinePlaneDepth::default(),
+        );
+        let depth = app.world().get::<OutlinePlaneDepth>(entity).unwrap();
+        assert!(depth
+            .model_plane_origin
+            .abs_diff_eq(Vec3::interpolate(&start, &end, t), 1e-5));
+        assert_eq!(depth.model_plane_offset, Vec3::ZERO);
+
+        let (app, entity, t) = animate(
+            OutlinePlaneDepthOffset,
+            start,
+            end,
+            OutlinePlaneDepth::default(),
+        );
+        let depth = app.world().get::<OutlinePlaneDepth>(entity).unwrap();
+        assert!(depth
+            .model_plane_offset
+            .abs_diff_eq(Vec3::interpolate(&start, &end, t), 1e-5));
+        assert_eq!(depth.model_plane_origin, Vec3::ZERO);
+    }
+}
@@ -9,6 +9,9 @@ struct OutlineViewUniform {
     aspect: f32,
     scale_clip_from_logical: vec2<f32>,
     scale_physical_from_logical: f32,
+    scale_logical_from_world: f32,
+    world_position: vec3<f32>,
+    time: f32,
 };
 
 struct VertexOutput {
@@ -17,12 +20,19 @@ struct VertexOutput {
     @location(0) @interpolate(flat) flat_depth: f32,
 #endif
 #ifdef VOLUME
+#ifdef VERTEX_COLOUR
+    @location(1) volume_colour: vec4<f32>,
+#else
     @location(1) @interpolate(flat) volume_colour: vec4<f32>,
 #endif
+#endif
 #ifdef ALPHA_MASK_TEXTURE
     @location(2) @interpolate(flat) alpha_mask_threshold: f32,
     @location(3) uv: vec2<f32>,
 #endif
+#ifdef FILL_TEXTURE
+    @location(4) @interpolate(flat) fill_scale: vec2<f32>,
+#endif
 };
 
 fn model_origin_z(plane: vec3<f32>, view_proj: mat4x4<f32>) -> f32 {
@@ -46,3 +56,38 @@ fn outline_flat_depth(
     let world_pos = world_plane_origin + model_eye * world_plane_offset;
     return model_origin_z(world_pos, view.clip_from_world);
 }
+
+// Converts a width to logical pixels, where the unit matches `OutlineWidthUnit`.
+fn outline_logical_width(
+    view: OutlineViewUniform,
+    width: f32,
+    unit: u32,
+    clip_w: f32,
+) -> f32 {
+    switch unit {
+        // Physical pixels
+        case 1u: {
+            return width / view.scale_physical_from_logical;
+        }
+        // World units
+        case 2u: {
+            return width * view.scale_logical_from_world / max(clip_w, 1e-6);
+        }
+        default: {
+            return width;
+        }
+    }
+}
+
+// Scales a width in logical pixels by the distance of the outline from the
+// camera, where scaling holds the near and far distances followed by the
+// minimum and maximum widths.
+fn outline_distance_scaled_width(width: f32, scaling: vec4<f32>, distance: f32) -> f32 {
+    let t = clamp((distance - scaling.x) / max(scaling.y - scaling.x, 1e-6), 0.0, 1.0);
+    return clamp(mix(width, scaling.z, t), scaling.z, max(scaling.w, scaling.z));
+}
+
+// Returns the opacity of an outline fading out beyond the far distance.
+fn outline_distance_fade(scaling: vec4<f32>, fade_out: f32, distance: f32) -> f32 {
+    return 1.0 - clamp((distance - scaling.y) / max(fade_out, 1e-6), 0.0, 1.0);
+}
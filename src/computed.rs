@@ -7,16 +7,29 @@ use bevy::{
 use crate::{
     pipeline_key::ComputedOutlineKey,
     uniforms::{DepthMode, DrawMode},
-    GlobalOutlineMode, InheritOutline, OutlineAlphaMask, OutlineFace, OutlineMode,
-    OutlinePlaneDepth, OutlineRenderLayers, OutlineStencil, OutlineStencilEnabled, OutlineVolume,
-    OutlineWarmUp, PropagateOutline,
+    GlobalOutlineMode, InheritOutline, OutlineAlphaMask, OutlineDashes, OutlineDistanceScaling,
+    OutlineFace, OutlineFillTexture, OutlineGlow, OutlineGradient, OutlineGroup, OutlineMode,
+    OutlineOccludedStyle, OutlinePlaneDepth, OutlineRenderLayers, OutlineRings, OutlineStencil,
+    OutlineStencilEnabled, OutlineStyleHandle, OutlineTransition, OutlineVolume, OutlineWarmUp,
+    OutlineWidthUnit, PropagateOutline,
 };
 
+#[cfg(any(feature = "sprite", feature = "ui"))]
+use crate::sprite::ComputedSpriteOutline;
+
 #[derive(Clone)]
 pub(crate) struct ComputedVolume {
     pub(crate) enabled: bool,
     pub(crate) offset: f32,
     pub(crate) colour: LinearRgba,
+    pub(crate) intensity: f32,
+}
+
+impl ComputedVolume {
+    /// Returns the colour scaled by the intensity, excluding alpha.
+    pub(crate) fn hdr_colour(&self) -> LinearRgba {
+        (self.colour * self.intensity).with_alpha(self.colour.alpha)
+    }
 }
 
 #[derive(Clone)]
@@ -65,15 +78,6 @@ impl<T: Clone> Sourced<T> {
         Self::set_with_fallback::<U, U>(value, None, &U::default(), inherit, f)
     }
 
-    pub fn set_with_default<U: Clone + Default>(
-        value: Option<Ref<U>>,
-        default: &U,
-        inherit: Option<T>,
-        f: impl FnOnce(&U) -> T,
-    ) -> Self {
-        Self::set_with_fallback::<U, U>(value, None, default, inherit, f)
-    }
-
     pub fn set_with_fallback<U, V: Clone + Into<U>>(
         value: Option<Ref<U>>,
         fallback: Option<Ref<V>>,
@@ -138,6 +142,66 @@ pub(crate) struct ComputedInternal {
     pub(crate) layers: Sourced<RenderLayers>,
     pub(crate) alpha_mask: Sourced<OutlineAlphaMask>,
     pub(crate) warm_up: Sourced<OutlineWarmUp>,
+    pub(crate) occluded: Sourced<ComputedVolume>,
+    pub(crate) rings: Sourced<Vec<ComputedVolume>>,
+    pub(crate) glow: Sourced<OutlineGlow>,
+    pub(crate) gradient: Sourced<OutlineGradient>,
+    pub(crate) width_unit: Sourced<OutlineWidthUnit>,
+    pub(crate) distance_scaling: Sourced<OutlineDistanceScaling>,
+    pub(crate) dashes: Sourced<OutlineDashes>,
+    pub(crate) fill_texture: Sourced<OutlineFillTexture>,
+    pub(crate) group: Sourced<Option<u32>>,
+}
+
+impl ComputedInternal {
+    fn is_flood(&self) -> bool {
+        #[cfg(feature = "flood")]
+        {
+            self.mode.value.draw_mode == DrawMode::JumpFlood
+        }
+        #[cfg(not(feature = "flood"))]
+        {
+            false
+        }
+    }
+
+    /// Returns whether the volume is drawn as a soft glow.
+    pub(crate) fn has_glow(&self) -> bool {
+        self.is_flood() && self.glow.value.falloff_width > 0.0
+    }
+
+    /// Returns whether the volume is drawn as dashes.
+    pub(crate) fn has_dashes(&self) -> bool {
+        self.is_flood() && self.dashes.value.dash_length > 0.0
+    }
+
+    /// Returns whether the volume is coloured with a gradient.
+    pub(crate) fn has_gradient(&self) -> bool {
+        self.is_flood()
+            && match &self.gradient.value {
+                OutlineGradient::Stops(stops) => !stops.is_empty(),
+                OutlineGradient::Texture(_) => true,
+            }
+    }
+
+    /// Returns the extent of the volume in logical pixels, including any glow.
+    pub(crate) fn volume_offset(&self) -> f32 {
+        if self.has_glow() {
+            self.glow.value.inner_width + self.glow.value.falloff_width
+        } else {
+            self.volume.value.offset
+        }
+    }
+}
+
+/// A component for storing a property of the [`OutlineStyle`](crate::OutlineStyle)
+/// applied to an entity.
+#[derive(Component)]
+pub(crate) struct StyledOutline<T: Send + Sync + 'static>(pub(crate) T);
+
+/// Returns a reference to the value of a styled property.
+fn styled<T: Send + Sync + 'static>(styled: Option<Ref<StyledOutline<T>>>) -> Option<Ref<T>> {
+    styled.map(|styled| Ref::map(styled, |styled| &styled.0))
 }
 
 /// A component for storing the computed depth at which the outline lies.
@@ -146,17 +210,19 @@ pub(crate) struct ComputedInternal {
 #[component(on_remove = remove_computed_outline_key)]
 pub struct ComputedOutline(pub(crate) Option<ComputedInternal>);
 
-/// Removes the required `ComputedOutlineKey` when `ComputedOutline` is removed.
+/// Removes the required `ComputedOutlineKey`, and the outline quad of any
+/// sprite, when `ComputedOutline` is removed.
 fn remove_computed_outline_key(mut world: DeferredWorld<'_>, context: HookContext) {
-    world
-        .commands()
-        .entity(context.entity)
-        .try_remove::<ComputedOutlineKey>();
+    let mut commands = world.commands();
+    let mut entity = commands.entity(context.entity);
+    entity.try_remove::<ComputedOutlineKey>();
+    #[cfg(any(feature = "sprite", feature = "ui"))]
+    entity.try_remove::<ComputedSpriteOutline>();
 }
 
 type OutlineComponents<'a> = (
     Ref<'a, InheritedVisibility>,
-    Ref<'a, GlobalTransform>,
+    Option<Ref<'a, GlobalTransform>>,
     Option<Ref<'a, OutlineVolume>>,
     Option<Ref<'a, OutlineStencil>>,
     Option<Ref<'a, OutlineMode>>,
@@ -166,6 +232,33 @@ type OutlineComponents<'a> = (
     Option<Ref<'a, RenderLayers>>,
     Option<Ref<'a, OutlineAlphaMask>>,
     Option<Ref<'a, OutlineWarmUp>>,
+    Option<Ref<'a, OutlineWidthUnit>>,
+    OutlineStyleComponents<'a>,
+);
+
+/// Additional styling components, nested to stay within the maximum tuple
+/// size supported by queries.
+type OutlineStyleComponents<'a> = (
+    Option<Ref<'a, OutlineOccludedStyle>>,
+    Option<Ref<'a, OutlineRings>>,
+    Option<Ref<'a, OutlineGlow>>,
+    Option<Ref<'a, OutlineGradient>>,
+    Option<Ref<'a, OutlineDistanceScaling>>,
+    Option<Ref<'a, OutlineDashes>>,
+    Option<Ref<'a, OutlineFillTexture>>,
+    Option<Ref<'a, OutlineGroup>>,
+    OutlineStyledComponents<'a>,
+);
+
+/// Properties taken from an [`OutlineStyle`](crate::OutlineStyle), which are
+/// used in place of any components not set directly on the entity.
+type OutlineStyledComponents<'a> = (
+    Option<Ref<'a, StyledOutline<OutlineVolume>>>,
+    Option<Ref<'a, StyledOutline<OutlineStencil>>>,
+    Option<Ref<'a, StyledOutline<OutlineMode>>>,
+    Option<Ref<'a, StyledOutline<OutlineFace>>>,
+    Option<Ref<'a, StyledOutline<OutlinePlaneDepth>>>,
+    Option<Ref<'a, StyledOutline<OutlineAlphaMask>>>,
 );
 
 #[allow(clippy::type_complexity)]
@@ -258,44 +351,83 @@ fn update_computed_outline(
         fallback_layers,
         alpha_mask,
         warm_up,
+        width_unit,
+        (occluded, rings, glow, gradient, distance_scaling, dashes, fill_texture, group, styles),
     ): QueryItem<'_, '_, OutlineComponents>,
     parent_computed: Option<&ComputedInternal>,
     parent_entity: Option<Entity>,
     force_update: bool,
     global_outline_mode: &GlobalOutlineMode,
 ) -> bool {
+    let (styled_volume, styled_stencil, styled_mode, styled_face, styled_depth, styled_alpha_mask) =
+        styles;
+    let styled_volume = styled(styled_volume);
+    let styled_stencil = styled(styled_stencil);
+    let styled_mode = styled(styled_mode);
+    let styled_face = styled(styled_face);
+    let styled_depth = styled(styled_depth);
+    let styled_alpha_mask = styled(styled_alpha_mask);
     let has_parent = parent_computed.is_some();
     let changed = force_update
         || if let ComputedOutline(Some(computed)) = computed.as_ref() {
             computed.inherited_from != parent_entity
                 || visibility.is_changed()
-                || transform.is_changed()
-                || computed.volume.is_changed(&volume, has_parent)
-                || computed.stencil.is_changed(&stencil, has_parent)
-                || computed.mode.is_changed(&mode, has_parent)
-                || computed.face.is_changed(&face, has_parent)
-                || computed.depth.is_changed(&depth, has_parent)
+                || transform.as_ref().is_some_and(|t| t.is_changed())
+                || computed
+                    .volume
+                    .is_changed_with_fallback(&volume, &styled_volume, has_parent)
+                || computed
+                    .stencil
+                    .is_changed_with_fallback(&stencil, &styled_stencil, has_parent)
+                || computed
+                    .mode
+                    .is_changed_with_fallback(&mode, &styled_mode, has_parent)
+                || computed
+                    .face
+                    .is_changed_with_fallback(&face, &styled_face, has_parent)
+                || computed
+                    .depth
+                    .is_changed_with_fallback(&depth, &styled_depth, has_parent)
                 || computed
                     .layers
                     .is_changed_with_fallback(&layers, &fallback_layers, has_parent)
-                || computed.alpha_mask.is_changed(&alpha_mask, has_parent)
+                || computed.alpha_mask.is_changed_with_fallback(
+                    &alpha_mask,
+                    &styled_alpha_mask,
+                    has_parent,
+                )
+                || computed.occluded.is_changed(&occluded, has_parent)
+                || computed.rings.is_changed(&rings, has_parent)
+                || computed.glow.is_changed(&glow, has_parent)
+                || computed.dashes.is_changed(&dashes, has_parent)
+                || computed.fill_texture.is_changed(&fill_texture, has_parent)
+                || computed.group.is_changed(&group, has_parent)
+                || computed.gradient.is_changed(&gradient, has_parent)
+                || computed.width_unit.is_changed(&width_unit, has_parent)
+                || computed
+                    .distance_scaling
+                    .is_changed(&distance_scaling, has_parent)
         } else {
             true
         };
     if changed {
         computed.0 = Some(ComputedInternal {
             inherited_from: parent_entity,
-            volume: Sourced::set(
+            volume: Sourced::set_with_fallback(
                 volume,
+                styled_volume,
+                &default(),
                 parent_computed.map(|p| p.volume.value.clone()),
                 |vol| ComputedVolume {
                     enabled: visibility.get() && vol.visible && !vol.colour.is_fully_transparent(),
                     offset: vol.width,
                     colour: vol.colour.into(),
+                    intensity: vol.intensity,
                 },
             ),
-            stencil: Sourced::set_with_default(
+            stencil: Sourced::set_with_fallback(
                 stencil,
+                styled_stencil,
                 &OutlineStencil::INHERIT_DEFAULT,
                 parent_computed.map(|p| p.stencil.value.clone()),
                 |sten| ComputedStencil {
@@ -307,8 +439,9 @@ fn update_computed_outline(
                     offset: sten.offset,
                 },
             ),
-            mode: Sourced::set_with_default(
+            mode: Sourced::set_with_fallback(
                 mode,
+                styled_mode,
                 &global_outline_mode.0,
                 parent_computed.map(|p| p.mode.value.clone()),
                 |mode| match mode {
@@ -325,21 +458,34 @@ fn update_computed_outline(
                         depth_mode: DepthMode::Flat,
                         draw_mode: DrawMode::JumpFlood,
                     },
+                    #[cfg(feature = "flood")]
+                    OutlineMode::FloodReal => ComputedMode {
+                        depth_mode: DepthMode::Real,
+                        draw_mode: DrawMode::JumpFlood,
+                    },
                 },
             ),
-            face: Sourced::set(
+            face: Sourced::set_with_fallback(
                 face,
+                styled_face,
+                &default(),
                 parent_computed.map(|p| p.face.value.clone()),
                 |face| ComputedFace {
                     double_sided: matches!(face, OutlineFace::DoubleSided),
                 },
             ),
-            depth: Sourced::set(
+            depth: Sourced::set_with_fallback(
                 depth,
+                styled_depth,
+                &default(),
                 parent_computed.map(|p| p.depth.value.clone()),
                 |dep| {
-                    let affine = transform.affine();
-                    let inverse = transform.affine().matrix3.inverse();
+                    // UI nodes have no transform, their depth being placed
+                    // later along with their outline quad.
+                    let affine = transform
+                        .as_deref()
+                        .map_or(Affine3A::IDENTITY, GlobalTransform::affine);
+                    let inverse = affine.matrix3.inverse();
                     ComputedDepth {
                         world_plane_origin: (affine
                             .matrix3
@@ -357,8 +503,10 @@ fn update_computed_outline(
                 parent_computed.map(|p| p.layers.value.clone()),
                 |layers| layers.0.clone(),
             ),
-            alpha_mask: Sourced::set(
+            alpha_mask: Sourced::set_with_fallback(
                 alpha_mask,
+                styled_alpha_mask,
+                &default(),
                 parent_computed.map(|p| p.alpha_mask.value.clone()),
                 |mask| mask.clone(),
             ),
@@ -367,6 +515,78 @@ fn update_computed_outline(
                 parent_computed.map(|p| p.warm_up.value.clone()),
                 |warm_up| warm_up.clone(),
             ),
+            occluded: Sourced::set(
+                occluded,
+                parent_computed.map(|p| p.occluded.value.clone()),
+                |occ| ComputedVolume {
+                    enabled: visibility.get() && occ.visible && !occ.colour.is_fully_transparent(),
+                    offset: occ.width,
+                    colour: occ.colour.into(),
+                    intensity: 1.0,
+                },
+            ),
+            rings: Sourced::set(
+                rings,
+                parent_computed.map(|p| p.rings.value.clone()),
+                |rings| {
+                    rings
+                        .iter()
+                        .map(|ring| ComputedVolume {
+                            enabled: visibility.get()
+                                && ring.visible
+                                && !ring.colour.is_fully_transparent(),
+                            offset: ring.width,
+                            colour: ring.colour.into(),
+                            intensity: ring.intensity,
+                        })
+                        .collect()
+                },
+            ),
+            glow: Sourced::set(
+                glow,
+                parent_computed.map(|p| p.glow.value.clone()),
+                |glow| glow.clone(),
+            ),
+            gradient: Sourced::set(
+                gradient,
+                parent_computed.map(|p| p.gradient.value.clone()),
+                |gradient| gradient.clone(),
+            ),
+            width_unit: Sourced::set(
+                width_unit,
+                parent_computed.map(|p| p.width_unit.value),
+                |width_unit| *width_unit,
+            ),
+            distance_scaling: Sourced::set(
+                distance_scaling,
+                parent_computed.map(|p| p.distance_scaling.value.clone()),
+                |scaling| scaling.clone(),
+            ),
+            dashes: Sourced::set(
+                dashes,
+                parent_computed.map(|p| p.dashes.value.clone()),
+                |dashes| dashes.clone(),
+            ),
+            fill_texture: Sourced::set(
+                fill_texture,
+                parent_computed.map(|p| p.fill_texture.value.clone()),
+                |fill| fill.clone(),
+            ),
+            // Unlike other properties, the default is to have no group
+            group: match (group, parent_computed) {
+                (Some(group), _) => Sourced {
+                    value: Some(group.0),
+                    source: Source::Set,
+                },
+                (None, Some(p)) => Sourced {
+                    value: p.group.value,
+                    source: Source::Inherited,
+                },
+                (None, None) => Sourced {
+                    value: None,
+                    source: Source::Default,
+                },
+            },
         });
     }
     changed
@@ -383,6 +603,8 @@ pub(crate) fn clean_up_computed_outline(
             Without<OutlineVolume>,
             Without<InheritOutline>,
             Without<PropagateOutline>,
+            Without<OutlineStyleHandle>,
+            Without<OutlineTransition>,
         ),
     >,
 ) {
@@ -398,6 +620,7 @@ mod tests {
     use bevy::ecs::system::RunSystemOnce;
 
     use super::*;
+    use crate::OutlineGlowCurve;
 
     fn setup() -> (App, Entity) {
         let mut app = App::new();
@@ -414,6 +637,25 @@ mod tests {
         (app, entity)
     }
 
+    fn spawn_inheriting_child(app: &mut App, parent: Entity) -> Entity {
+        app.world_mut()
+            .spawn((
+                InheritOutline,
+                ComputedOutline::default(),
+                InheritedVisibility::VISIBLE,
+                GlobalTransform::default(),
+            ))
+            .insert(ChildOf(parent))
+            .id()
+    }
+
+    fn computed_internal(app: &App, entity: Entity) -> &ComputedInternal {
+        app.world()
+            .get::<ComputedOutline>(entity)
+            .and_then(|computed| computed.0.as_ref())
+            .expect("ComputedOutline should have Some value after update")
+    }
+
     #[test]
     fn test_computed_outline_key_added_and_removed() {
         let (mut app, entity) = setup();
@@ -497,6 +739,7 @@ mod tests {
             visible: true,
             width: 2.0,
             colour: Color::WHITE,
+            ..default()
         };
         app.world_mut().entity_mut(parent).insert(volume.clone());
 
@@ -532,6 +775,95 @@ mod tests {
         assert_eq!(child_internal.inherited_from, Some(parent));
     }
 
+    #[test]
+    fn test_styled_volume_priority() {
+        let (mut app, entity) = setup();
+
+        // A styled volume applies when no volume is set directly.
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(StyledOutline(OutlineVolume {
+                visible: true,
+                width: 3.0,
+                colour: Color::WHITE,
+                ..default()
+            }));
+        app.update();
+
+        let computed = app.world().get::<ComputedOutline>(entity).unwrap();
+        let internal = computed.0.as_ref().unwrap();
+        assert!(internal.volume.value.enabled);
+        assert_eq!(internal.volume.value.offset, 3.0);
+        assert_eq!(internal.volume.source, Source::SetFallback);
+
+        // A directly set volume takes priority over the style.
+        app.world_mut().entity_mut(entity).insert(OutlineVolume {
+            visible: true,
+            width: 5.0,
+            colour: Color::WHITE,
+            ..default()
+        });
+        app.update();
+
+        let computed = app.world().get::<ComputedOutline>(entity).unwrap();
+        let internal = computed.0.as_ref().unwrap();
+        assert_eq!(internal.volume.value.offset, 5.0);
+        assert_eq!(internal.volume.source, Source::Set);
+    }
+
+    #[test]
+    fn test_ring_intensity() {
+        let (mut app, entity) = setup();
+
+        app.world_mut().entity_mut(entity).insert((
+            OutlineVolume {
+                visible: true,
+                width: 2.0,
+                colour: Color::WHITE,
+                ..default()
+            },
+            OutlineRings(vec![OutlineVolume {
+                visible: true,
+                width: 4.0,
+                colour: Color::linear_rgba(0.5, 0.25, 1.0, 0.5),
+                intensity: 4.0,
+            }]),
+        ));
+        app.update();
+
+        // The intensity scales the colour of the ring, but not its alpha.
+        let computed = app.world().get::<ComputedOutline>(entity).unwrap();
+        let internal = computed.0.as_ref().unwrap();
+        assert_eq!(
+            internal.rings.value[0].hdr_colour(),
+            LinearRgba::new(2.0, 1.0, 4.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn test_occluded_style_propagation() {
+        let (mut app, parent) = setup();
+        let child = spawn_inheriting_child(&mut app, parent);
+        app.update();
+        assert!(!computed_internal(&app, child).occluded.value.enabled);
+
+        // Add an OutlineOccludedStyle to the parent
+        app.world_mut()
+            .entity_mut(parent)
+            .insert(OutlineOccludedStyle {
+                visible: true,
+                width: 4.0,
+                colour: Color::BLACK.with_alpha(0.5),
+            });
+        app.update();
+
+        let internal = computed_internal(&app, child);
+        assert!(internal.occluded.value.enabled);
+        assert_eq!(internal.occluded.value.offset, 4.0);
+        assert_eq!(internal.occluded.value.colour.alpha, 0.5);
+        assert_eq!(internal.occluded.source, Source::Inherited);
+    }
+
     #[test]
     fn test_clean_up_computed_outline() {
         let (mut app, entity) = setup();
@@ -565,4 +897,158 @@ mod tests {
             "ComputedOutlineKey should be removed alongside ComputedOutline"
         );
     }
+
+    #[test]
+    fn test_rings_propagation() {
+        let (mut app, parent) = setup();
+        let child = spawn_inheriting_child(&mut app, parent);
+        let ring = OutlineVolume {
+            visible: true,
+            width: 6.0,
+            colour: Color::WHITE,
+            ..default()
+        };
+        app.world_mut().entity_mut(parent).insert((
+            OutlineVolume {
+                visible: true,
+                width: 2.0,
+                colour: Color::BLACK,
+                ..default()
+            },
+            OutlineRings(vec![ring.clone()]),
+        ));
+        app.update();
+
+        let internal = computed_internal(&app, child);
+        assert_eq!(internal.rings.source, Source::Inherited);
+        assert_eq!(internal.rings.value.len(), 1);
+        assert!(internal.rings.value[0].enabled);
+        assert_eq!(internal.rings.value[0].offset, 6.0);
+
+        // Changes to the parent's rings reach the child
+        app.world_mut()
+            .entity_mut(parent)
+            .insert(OutlineRings(vec![ring.clone(), ring.clone()]));
+        app.update();
+        assert_eq!(computed_internal(&app, child).rings.value.len(), 2);
+
+        // Rings set on the child take priority
+        app.world_mut()
+            .entity_mut(child)
+            .insert(OutlineRings(Vec::new()));
+        app.update();
+        let internal = computed_internal(&app, child);
+        assert_eq!(internal.rings.source, Source::Set);
+        assert!(internal.rings.value.is_empty());
+    }
+
+    #[test]
+    fn test_glow_propagation() {
+        let (mut app, parent) = setup();
+        let child = spawn_inheriting_child(&mut app, parent);
+        app.world_mut().entity_mut(parent).insert(OutlineGlow {
+            inner_width: 2.0,
+            falloff_width: 8.0,
+            curve: OutlineGlowCurve::Linear,
+        });
+        app.update();
+
+        // A glow set on the child takes priority
+        app.world_mut().entity_mut(child).insert(OutlineGlow {
+            inner_width: 0.0,
+            falloff_width: 4.0,
+            ..default()
+        });
+        app.update();
+        let internal = computed_internal(&app, child);
+        assert_eq!(internal.glow.source, Source::Set);
+        assert_eq!(internal.glow.value.falloff_width, 4.0);
+        assert_eq!(internal.glow.value.curve, OutlineGlowCurve::Smooth);
+
+        // Removing it falls back to the parent's glow
+        app.world_mut().entity_mut(child).remove::<OutlineGlow>();
+        app.update();
+        let internal = computed_internal(&app, child);
+        assert_eq!(internal.glow.source, Source::Inherited);
+        assert_eq!(internal.glow.value.falloff_width, 8.0);
+        assert_eq!(internal.glow.value.curve, OutlineGlowCurve::Linear);
+    }
+
+    #[test]
+    fn test_width_unit_propagation() {
+        let (mut app, parent) = setup();
+        let child = spawn_inheriting_child(&mut app, parent);
+        let grandchild = spawn_inheriting_child(&mut app, child);
+        app.update();
+        assert_eq!(
+            computed_internal(&app, grandchild).width_unit.value,
+            OutlineWidthUnit::LogicalPixels
+        );
+
+        // The unit is passed down through each inheriting generation
+        app.world_mut()
+            .entity_mut(parent)
+            .insert(OutlineWidthUnit::WorldUnits);
+        app.update();
+        let internal = computed_internal(&app, grandchild);
+        assert_eq!(internal.width_unit.source, Source::Inherited);
+        assert_eq!(internal.width_unit.value, OutlineWidthUnit::WorldUnits);
+        assert_eq!(internal.inherited_from, Some(child));
+    }
+
+    #[test]
+    fn test_distance_scaling_propagation() {
+        let (mut app, parent) = setup();
+        let child = spawn_inheriting_child(&mut app, parent);
+        let other_child = app
+            .world_mut()
+            .spawn((
+                ComputedOutline::default(),
+                InheritedVisibility::VISIBLE,
+                GlobalTransform::default(),
+            ))
+            .insert(ChildOf(parent))
+            .id();
+        let scaling = OutlineDistanceScaling {
+            near: 10.0,
+            far: 20.0,
+            min_width: 1.0,
+            ..default()
+        };
+        app.world_mut().entity_mut(parent).insert(scaling.clone());
+        app.update();
+
+        let internal = computed_internal(&app, child);
+        assert_eq!(internal.distance_scaling.source, Source::Inherited);
+        assert_eq!(internal.distance_scaling.value, scaling);
+
+        // Children without InheritOutline are unaffected
+        let internal = computed_internal(&app, other_child);
+        assert_eq!(internal.distance_scaling.source, Source::Default);
+        assert_eq!(
+            internal.distance_scaling.value,
+            OutlineDistanceScaling::default()
+        );
+    }
+
+    #[test]
+    fn test_group_propagation() {
+        let (mut app, parent) = setup();
+        let child = spawn_inheriting_child(&mut app, parent);
+        app.update();
+        assert_eq!(computed_internal(&app, child).group.value, None);
+
+        app.world_mut().entity_mut(parent).insert(OutlineGroup(1));
+        app.update();
+        let internal = computed_internal(&app, child);
+        assert_eq!(internal.group.source, Source::Inherited);
+        assert_eq!(internal.group.value, Some(1));
+
+        app.world_mut().entity_mut(child).insert(OutlineGroup(2));
+        app.update();
+        let internal = computed_internal(&app, child);
+        assert_eq!(internal.group.source, Source::Set);
+        assert_eq!(internal.group.value, Some(2));
+        assert_eq!(computed_internal(&app, parent).group.value, Some(1));
+    }
 }
@@ -12,6 +12,8 @@ use bevy::render::view::{
 use bevy::render::Extract;
 
 use crate::computed::ComputedOutline;
+use crate::uniforms::{outline_world_from_local, OutlineQuadData};
+use crate::view_uniforms::scale_logical_from_world;
 
 /// Per-entity, per-view information collected during visibility checking.
 #[derive(Default, Clone, Copy, Debug)]
@@ -67,7 +69,8 @@ pub(crate) fn check_outline_view_visibility(
         Entity,
         &ComputedOutline,
         Option<&Aabb>,
-        &GlobalTransform,
+        Option<&GlobalTransform>,
+        OutlineQuadData,
         Has<NoFrustumCulling>,
         &mut ViewVisibility,
     )>,
@@ -93,9 +96,19 @@ pub(crate) fn check_outline_view_visibility(
         let view_from_world = view_transform.to_matrix().inverse();
         let clip_from_world = camera.clip_from_view() * view_from_world;
         let scale_factor = camera.target_scaling_factor().unwrap_or(1.0);
-
-        for (entity, computed, aabb, transform, no_frustum_culling, mut entity_in_view) in
-            outlines.iter_mut()
+        let scale_world = camera
+            .logical_viewport_size()
+            .map_or(0.0, |size| scale_logical_from_world(camera, size));
+
+        for (
+            entity,
+            computed,
+            aabb,
+            transform,
+            sprite_outline,
+            no_frustum_culling,
+            mut entity_in_view,
+        ) in outlines.iter_mut()
         {
             let Some(computed) = &computed.0 else {
                 continue;
@@ -113,7 +126,18 @@ pub(crate) fn check_outline_view_visibility(
                 continue;
             }
 
-            let world_from_local = transform.affine();
+            // UI nodes are only visible to their target camera.
+            #[cfg(any(feature = "sprite", feature = "ui"))]
+            if sprite_outline
+                .and_then(|sprite_outline| sprite_outline.camera)
+                .is_some_and(|camera| camera != view_entity)
+            {
+                continue;
+            }
+
+            let Some(world_from_local) = outline_world_from_local(transform, sprite_outline) else {
+                continue;
+            };
 
             let screen_space_bounds = if let (Some(aabb), false) = (aabb, no_frustum_culling) {
                 // 3) Mesh AABB at least partly in front of the near plane.
@@ -126,7 +150,33 @@ pub(crate) fn check_outline_view_visibility(
                 }
 
                 // 4) Compute screen-space bounds and check overlap with the viewport.
-                let border = (scale_factor * computed.volume.value.offset).ceil() as u32;
+                let mut offset = computed.volume_offset();
+                if computed.occluded.value.enabled {
+                    offset = offset.max(computed.occluded.value.offset);
+                }
+                for ring in computed.rings.value.iter().filter(|ring| ring.enabled) {
+                    offset = offset.max(ring.offset);
+                }
+                // Take widths in world units at the nearest point of the AABB,
+                // where they are widest on screen.
+                let w_row = clip_from_world.row(3);
+                let clip_w = w_row.dot(aabb_center_world)
+                    - aabb.relative_radius(&w_row.xyz().into(), &world_from_local.matrix3);
+                let offset =
+                    computed
+                        .width_unit
+                        .value
+                        .to_logical(offset, scale_factor, scale_world, clip_w);
+                let distance = computed
+                    .depth
+                    .value
+                    .world_plane_origin
+                    .distance(view_transform.translation());
+                let offset = computed
+                    .distance_scaling
+                    .value
+                    .scale_width(offset, distance);
+                let border = (scale_factor * offset).ceil() as u32;
                 let Some(bounds) = compute_screen_space_bounds(
                     aabb,
                     &world_from_local,
@@ -0,0 +1,16 @@
+#define_import_path bevy_mod_outline::extrude
+
+#import bevy_mod_outline::common::OutlineViewUniform
+
+// Returns the direction in clip space in which a vertex is extruded, given
+// its position and outline normal in clip space.
+fn outline_extrude_direction(view: OutlineViewUniform, clip_pos: vec4<f32>, clip_norm: vec3<f32>) -> vec2<f32> {
+    return normalize(clip_norm.xy * vec2<f32>(view.aspect, 1.0));
+}
+
+// Returns the offset in clip space of an extruded vertex, given its position
+// in clip space, the direction of extrusion, and the outline width in logical
+// pixels.
+fn outline_extrude_offset(view: OutlineViewUniform, clip_pos: vec4<f32>, direction: vec2<f32>, logical_width: f32) -> vec2<f32> {
+    return logical_width * direction * view.scale_clip_from_logical * clip_pos.w;
+}
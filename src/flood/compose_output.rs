@@ -4,30 +4,35 @@ use bevy::{
     prelude::*,
     render::{
         extract_component::{ComponentUniforms, DynamicUniformIndex},
+        render_asset::RenderAssets,
         render_resource::{
-            binding_types::{texture_2d, uniform_buffer},
-            BindGroupEntries, BindGroupLayoutDescriptor, BindGroupLayoutEntries,
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            BindGroup, BindGroupEntries, BindGroupLayoutDescriptor, BindGroupLayoutEntries,
             CachedRenderPipelineId, DynamicUniformBuffer, FragmentState, PipelineCache,
             RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, ShaderType,
             VertexState,
         },
         renderer::{RenderContext, RenderDevice, RenderQueue},
         sync_world::MainEntity,
-        texture::CachedTexture,
+        texture::{CachedTexture, FallbackImage, GpuImage},
         view::{ExtractedView, ViewDepthTexture, ViewTarget},
     },
 };
 use wgpu_types::{
     BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState,
-    MultisampleState, PrimitiveState, ShaderStages, StencilState, TextureFormat, TextureSampleType,
+    MultisampleState, PrimitiveState, SamplerBindingType, ShaderStages, StencilState,
+    TextureFormat, TextureSampleType,
 };
 
 use crate::{
     culling::RenderExtractedOutlineEntities,
     msaa::{OutlineViewTextures, ResolvedOutlineMsaa},
     node::{outline_colour_attachment, outline_depth_attachment},
+    occlusion::{scene_depth_binding, ResolvedSceneDepth},
     pipeline_key::ViewPipelineKey,
-    uniforms::RenderOutlineInstances,
+    render_target::OutlineTargetView,
+    uniforms::{DepthMode, ExtractedGradient, ExtractedOutline, RenderOutlineInstances},
+    OutlineGradient, OutlineTextureMapping,
 };
 
 use super::{DrawMode, OutlineViewUniform, COMPOSE_OUTPUT_SHADER_HANDLE};
@@ -38,12 +43,67 @@ pub(crate) struct ComposeOutputUniform {
     pub world_plane_offset: Vec3,
     pub volume_offset: f32,
     pub volume_colour: Vec4,
+    pub occluded_offset: f32,
+    pub occluded_colour: Vec4,
+    pub glow_inner: f32,
+    pub glow_falloff: f32,
+    pub glow_curve: u32,
+    pub gradient_mode: u32,
+    pub gradient_stop_count: u32,
+    pub gradient_positions: [Vec4; 2],
+    pub gradient_colours: [Vec4; OutlineGradient::MAX_STOPS],
+    pub width_unit: u32,
+    pub distance_scaling: Vec4,
+    pub distance_fade: f32,
+    pub dash_length: f32,
+    pub dash_gap: f32,
+    pub dash_speed: f32,
+    pub fill_mode: u32,
+    pub fill_tile_size: Vec2,
+    pub real_depth: u32,
+}
+
+const FILL_NONE: u32 = 0;
+const FILL_SCREEN: u32 = 1;
+const FILL_ALONG_OUTLINE: u32 = 2;
+
+fn fill_mode(outline: &ExtractedOutline) -> u32 {
+    match (outline.fill_texture_id, outline.fill_mapping) {
+        (None, _) => FILL_NONE,
+        (Some(_), OutlineTextureMapping::Screen) => FILL_SCREEN,
+        (Some(_), OutlineTextureMapping::AlongOutline) => FILL_ALONG_OUTLINE,
+    }
+}
+
+const GRADIENT_NONE: u32 = 0;
+const GRADIENT_STOPS: u32 = 1;
+const GRADIENT_TEXTURE: u32 = 2;
+
+impl ComposeOutputUniform {
+    fn with_gradient(mut self, gradient: Option<&ExtractedGradient>) -> Self {
+        match gradient {
+            None => {}
+            Some(ExtractedGradient::Stops(stops)) => {
+                self.gradient_mode = GRADIENT_STOPS;
+                self.gradient_stop_count = stops.len() as u32;
+                for (index, (position, colour)) in stops.iter().enumerate() {
+                    self.gradient_positions[index / 4][index % 4] = *position;
+                    self.gradient_colours[index] = *colour;
+                }
+            }
+            Some(ExtractedGradient::Texture(_)) => {
+                self.gradient_mode = GRADIENT_TEXTURE;
+            }
+        }
+        self
+    }
 }
 
 #[derive(Resource, Default)]
 pub(crate) struct ComposeOutputUniforms {
     pub buffer: DynamicUniformBuffer<ComposeOutputUniform>,
-    pub offsets: HashMap<(Entity, MainEntity), u32>,
+    /// Offsets for each view, entity, and ring, where ring zero is the volume.
+    pub offsets: HashMap<(Entity, MainEntity, usize), u32>,
 }
 
 pub(crate) fn prepare_compose_output_uniform(
@@ -71,13 +131,70 @@ pub(crate) fn prepare_compose_output_uniform(
             if outline.draw_mode != DrawMode::JumpFlood {
                 continue;
             }
-            let offset = uniforms.buffer.push(&ComposeOutputUniform {
-                world_plane_origin: outline.instance_data.world_plane_origin,
-                world_plane_offset: outline.instance_data.world_plane_offset,
-                volume_offset: outline.instance_data.volume_offset,
-                volume_colour: outline.instance_data.volume_colour,
-            });
-            uniforms.offsets.insert((view_entity, *main_entity), offset);
+            let offset = uniforms.buffer.push(
+                &ComposeOutputUniform {
+                    world_plane_origin: outline.instance_data.world_plane_origin,
+                    world_plane_offset: outline.instance_data.world_plane_offset,
+                    volume_offset: outline.instance_data.volume_offset,
+                    volume_colour: outline.instance_data.volume_colour,
+                    occluded_offset: outline.instance_data.occluded_offset,
+                    occluded_colour: if outline.occluded {
+                        outline.instance_data.occluded_colour
+                    } else {
+                        Vec4::ZERO
+                    },
+                    glow_inner: outline.glow.map_or(0.0, |glow| glow.inner),
+                    glow_falloff: outline.glow.map_or(0.0, |glow| glow.falloff),
+                    glow_curve: outline.glow.map_or(0, |glow| glow.curve),
+                    gradient_mode: GRADIENT_NONE,
+                    gradient_stop_count: 0,
+                    gradient_positions: [Vec4::ZERO; 2],
+                    gradient_colours: [Vec4::ZERO; OutlineGradient::MAX_STOPS],
+                    width_unit: outline.instance_data.width_unit,
+                    distance_scaling: outline.instance_data.distance_scaling,
+                    distance_fade: outline.instance_data.distance_fade,
+                    dash_length: outline.dashes.map_or(0.0, |dashes| dashes.length),
+                    dash_gap: outline.dashes.map_or(0.0, |dashes| dashes.gap),
+                    dash_speed: outline.dashes.map_or(0.0, |dashes| dashes.speed),
+                    fill_mode: fill_mode(outline),
+                    fill_tile_size: outline.instance_data.fill_tile_size,
+                    real_depth: (outline.depth_mode == DepthMode::Real) as u32,
+                }
+                .with_gradient(outline.gradient.as_ref()),
+            );
+            uniforms
+                .offsets
+                .insert((view_entity, *main_entity, 0), offset);
+            for (index, ring) in outline.rings.iter().enumerate() {
+                // Only the volume is drawn where the outline is occluded
+                let offset = uniforms.buffer.push(&ComposeOutputUniform {
+                    world_plane_origin: outline.instance_data.world_plane_origin,
+                    world_plane_offset: outline.instance_data.world_plane_offset,
+                    volume_offset: ring.offset,
+                    volume_colour: ring.colour,
+                    occluded_offset: 0.0,
+                    occluded_colour: Vec4::ZERO,
+                    glow_inner: 0.0,
+                    glow_falloff: 0.0,
+                    glow_curve: 0,
+                    gradient_mode: GRADIENT_NONE,
+                    gradient_stop_count: 0,
+                    gradient_positions: [Vec4::ZERO; 2],
+                    gradient_colours: [Vec4::ZERO; OutlineGradient::MAX_STOPS],
+                    width_unit: outline.instance_data.width_unit,
+                    distance_scaling: outline.instance_data.distance_scaling,
+                    distance_fade: outline.instance_data.distance_fade,
+                    dash_length: 0.0,
+                    dash_gap: 0.0,
+                    dash_speed: 0.0,
+                    fill_mode: FILL_NONE,
+                    fill_tile_size: Vec2::ONE,
+                    real_depth: (outline.depth_mode == DepthMode::Real) as u32,
+                });
+                uniforms
+                    .offsets
+                    .insert((view_entity, *main_entity, index + 1), offset);
+            }
         }
     }
     uniforms.buffer.write_buffer(&render_device, &render_queue);
@@ -86,6 +203,8 @@ pub(crate) fn prepare_compose_output_uniform(
 #[derive(Clone, Resource)]
 pub(crate) struct ComposeOutputPipeline {
     pub(crate) layout: BindGroupLayoutDescriptor,
+    pub(crate) scene_depth_layouts: [BindGroupLayoutDescriptor; 2],
+    pub(crate) texture_layout: BindGroupLayoutDescriptor,
     pub(crate) pipeline_cache: HashMap<ViewPipelineKey, CachedRenderPipelineId>,
 }
 
@@ -102,13 +221,50 @@ pub(crate) fn init_compose_output_pipeline(mut commands: Commands) {
         ),
     );
 
+    let scene_depth_layouts = [false, true].map(|multisampled| {
+        BindGroupLayoutDescriptor::new(
+            "outline_flood_compose_output_scene_depth_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::VERTEX_FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    uniform_buffer::<OutlineViewUniform>(true),
+                    uniform_buffer::<ComposeOutputUniform>(true),
+                    scene_depth_binding(multisampled),
+                ),
+            ),
+        )
+    });
+
+    let texture_layout = BindGroupLayoutDescriptor::new(
+        "outline_flood_compose_output_texture_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+            ),
+        ),
+    );
+
     commands.insert_resource(ComposeOutputPipeline {
         layout,
+        scene_depth_layouts,
+        texture_layout,
         pipeline_cache: HashMap::new(),
     });
 }
 
 impl ComposeOutputPipeline {
+    fn get_layout(&self, key: ViewPipelineKey) -> &BindGroupLayoutDescriptor {
+        if key.scene_depth() {
+            &self.scene_depth_layouts[key.scene_depth_multisampled() as usize]
+        } else {
+            &self.layout
+        }
+    }
+
     pub(crate) fn get_pipeline(
         &mut self,
         pipeline_cache: &PipelineCache,
@@ -119,9 +275,16 @@ impl ComposeOutputPipeline {
             if key.msaa().samples() > 1 {
                 shader_defs.push(ShaderDefVal::from("MSAA"));
             }
+            if key.scene_depth() {
+                shader_defs.push(ShaderDefVal::from("SCENE_DEPTH"));
+                if key.scene_depth_multisampled() {
+                    shader_defs.push(ShaderDefVal::from("SCENE_DEPTH_MULTISAMPLED"));
+                }
+            }
+            let layout = self.get_layout(key).clone();
             pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
                 label: Some("outline_flood_compose_output_pipeline".into()),
-                layout: vec![self.layout.clone()],
+                layout: vec![layout, self.texture_layout.clone()],
                 vertex: VertexState {
                     shader: COMPOSE_OUTPUT_SHADER_HANDLE,
                     shader_defs: shader_defs.clone(),
@@ -158,39 +321,130 @@ impl ComposeOutputPipeline {
     }
 }
 
+/// Texture bind groups for outline volumes, keyed by their gradient and fill
+/// textures.
+#[derive(Resource)]
+pub(crate) struct ComposeOutputTextureBindGroups {
+    pub bind_groups: HashMap<(Option<AssetId<Image>>, Option<AssetId<Image>>), BindGroup>,
+    /// The bind group of outlines without a gradient or fill texture.
+    pub default_bind_group: BindGroup,
+}
+
+fn texture_key(outline: &ExtractedOutline) -> (Option<AssetId<Image>>, Option<AssetId<Image>>) {
+    let gradient_id = match outline.gradient {
+        Some(ExtractedGradient::Texture(id)) => Some(id),
+        _ => None,
+    };
+    (gradient_id, outline.fill_texture_id)
+}
+
+pub(crate) fn init_compose_output_texture_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    fallback_image: Res<FallbackImage>,
+    compose_output_pipeline: Res<ComposeOutputPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    commands.insert_resource(ComposeOutputTextureBindGroups {
+        bind_groups: HashMap::new(),
+        default_bind_group: render_device.create_bind_group(
+            "default_outline_flood_compose_output_texture_bind_group",
+            &pipeline_cache.get_bind_group_layout(&compose_output_pipeline.texture_layout),
+            &BindGroupEntries::sequential((
+                &fallback_image.d2.texture_view,
+                &fallback_image.d2.texture_view,
+                &fallback_image.d2.sampler,
+            )),
+        ),
+    });
+}
+
+pub(crate) fn prepare_compose_output_texture_bind_groups(
+    mut texture_bind_groups: ResMut<ComposeOutputTextureBindGroups>,
+    render_device: Res<RenderDevice>,
+    compose_output_pipeline: Res<ComposeOutputPipeline>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    fallback_image: Res<FallbackImage>,
+    render_outlines: Res<RenderOutlineInstances>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    texture_bind_groups.bind_groups.clear();
+
+    for outline in render_outlines.entity_map.values() {
+        if outline.draw_mode != DrawMode::JumpFlood {
+            continue;
+        }
+        let (gradient_id, fill_id) = texture_key(outline);
+        let gradient_image = gradient_id.and_then(|id| gpu_images.get(id));
+        let fill_image = fill_id.and_then(|id| gpu_images.get(id));
+        if gradient_image.is_none() && fill_image.is_none() {
+            continue;
+        }
+        texture_bind_groups
+            .bind_groups
+            .entry((gradient_id, fill_id))
+            .or_insert_with(|| {
+                let gradient_texture = gradient_image
+                    .map_or(&fallback_image.d2.texture_view, |image| &image.texture_view);
+                let (fill_texture, fill_sampler) = fill_image.map_or(
+                    (&fallback_image.d2.texture_view, &fallback_image.d2.sampler),
+                    |image| (&image.texture_view, &image.sampler),
+                );
+                render_device.create_bind_group(
+                    "outline_flood_compose_output_texture_bind_group",
+                    &pipeline_cache.get_bind_group_layout(&compose_output_pipeline.texture_layout),
+                    &BindGroupEntries::sequential((gradient_texture, fill_texture, fill_sampler)),
+                )
+            });
+    }
+}
+
 #[derive(Component)]
 pub struct ComposeOutputView {
     pub(crate) pipeline_id: CachedRenderPipelineId,
+    pub(crate) key: ViewPipelineKey,
 }
 
+#[allow(clippy::type_complexity)]
 pub(crate) fn prepare_compose_output_pass(
     mut commands: Commands,
-    query: Query<(Entity, &ExtractedView, &ResolvedOutlineMsaa), With<OutlineViewUniform>>,
+    query: Query<
+        (
+            Entity,
+            &ExtractedView,
+            &ResolvedOutlineMsaa,
+            Option<&ResolvedSceneDepth>,
+            Option<&OutlineTargetView>,
+        ),
+        With<OutlineViewUniform>,
+    >,
     pipeline_cache: Res<PipelineCache>,
     mut compose_output_pipeline: ResMut<ComposeOutputPipeline>,
 ) {
-    for (entity, view, msaa) in query.iter() {
-        let pipeline_id = compose_output_pipeline.get_pipeline(
-            &pipeline_cache,
-            ViewPipelineKey::new()
-                .with_msaa(**msaa)
-                .with_target_format(view.target_format),
-        );
+    for (entity, view, msaa, scene_depth, target_view) in query.iter() {
+        let key = ViewPipelineKey::new()
+            .with_msaa(**msaa)
+            .with_target_format(target_view.map_or(view.target_format, |t| t.format))
+            .with_scene_depth(scene_depth.map(|scene_depth| **scene_depth));
+        let pipeline_id = compose_output_pipeline.get_pipeline(&pipeline_cache, key);
         commands
             .entity(entity)
-            .insert(ComposeOutputView { pipeline_id });
+            .insert(ComposeOutputView { pipeline_id, key });
     }
 }
 
 pub(crate) struct ComposeOutputPass<'w> {
     world: &'w World,
     pipeline: &'w ComposeOutputPipeline,
+    key: ViewPipelineKey,
     render_pipeline: &'w RenderPipeline,
     outline_view_uniforms: &'w ComponentUniforms<OutlineViewUniform>,
     compose_output_uniforms: &'w ComposeOutputUniforms,
     view_target: &'w ViewTarget,
     view_depth: &'w ViewDepthTexture,
     outline_textures: Option<&'w OutlineViewTextures>,
+    render_outlines: &'w RenderOutlineInstances,
+    texture_bind_groups: &'w ComposeOutputTextureBindGroups,
 }
 
 impl<'w> ComposeOutputPass<'w> {
@@ -207,24 +461,31 @@ impl<'w> ComposeOutputPass<'w> {
             pipeline_cache.get_render_pipeline(compose_output_view.pipeline_id)?;
         let outline_view_uniforms = world.resource::<ComponentUniforms<OutlineViewUniform>>();
         let compose_output_uniforms = world.resource::<ComposeOutputUniforms>();
+        let render_outlines = world.resource::<RenderOutlineInstances>();
+        let texture_bind_groups = world.resource::<ComposeOutputTextureBindGroups>();
 
         Some(Self {
             world,
             pipeline,
+            key: compose_output_view.key,
             render_pipeline,
             outline_view_uniforms,
             compose_output_uniforms,
             view_target,
             view_depth,
             outline_textures,
+            render_outlines,
+            texture_bind_groups,
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn execute(
         &self,
         render_context: &mut RenderContext<'_, '_>,
         view_entity: Entity,
         main_entity: MainEntity,
+        ring: usize,
         input: &CachedTexture,
         pipeline_cache: &PipelineCache,
         bounds: &URect,
@@ -238,18 +499,47 @@ impl<'w> ComposeOutputPass<'w> {
         let dynamic_index = *self
             .compose_output_uniforms
             .offsets
-            .get(&(view_entity, main_entity))
+            .get(&(view_entity, main_entity, ring))
             .unwrap();
 
-        let bind_group = render_context.render_device().create_bind_group(
-            "outline_flood_compose_output_bind_group",
-            &pipeline_cache.get_bind_group_layout(&self.pipeline.layout),
-            &BindGroupEntries::sequential((
-                &input.default_view,
-                self.outline_view_uniforms.binding().unwrap(),
-                self.compose_output_uniforms.buffer.binding().unwrap(),
-            )),
-        );
+        let layout = pipeline_cache.get_bind_group_layout(self.pipeline.get_layout(self.key));
+        let view_binding = self.outline_view_uniforms.binding().unwrap();
+        let compose_output_binding = self.compose_output_uniforms.buffer.binding().unwrap();
+        let bind_group = if self.key.scene_depth() {
+            render_context.render_device().create_bind_group(
+                "outline_flood_compose_output_bind_group",
+                &layout,
+                &BindGroupEntries::sequential((
+                    &input.default_view,
+                    view_binding,
+                    compose_output_binding,
+                    self.view_depth.view(),
+                )),
+            )
+        } else {
+            render_context.render_device().create_bind_group(
+                "outline_flood_compose_output_bind_group",
+                &layout,
+                &BindGroupEntries::sequential((
+                    &input.default_view,
+                    view_binding,
+                    compose_output_binding,
+                )),
+            )
+        };
+
+        // Only the volume has a gradient or fill texture, so rings always use
+        // the default
+        let texture_bind_group = self
+            .render_outlines
+            .get(&main_entity)
+            .filter(|_| ring == 0)
+            .and_then(|outline| {
+                self.texture_bind_groups
+                    .bind_groups
+                    .get(&texture_key(outline))
+            })
+            .unwrap_or(&self.texture_bind_groups.default_bind_group);
 
         let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
             label: Some("outline_flood_compose_output_pass"),
@@ -269,6 +559,7 @@ impl<'w> ComposeOutputPass<'w> {
         render_pass.set_scissor_rect(bounds.min.x, bounds.min.y, bounds.width(), bounds.height());
         render_pass.set_render_pipeline(self.render_pipeline);
         render_pass.set_bind_group(0, &bind_group, &[view_dynamic_index, dynamic_index]);
+        render_pass.set_bind_group(1, texture_bind_group, &[]);
         render_pass.draw(0..3, 0..1);
     }
 }
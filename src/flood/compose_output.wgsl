@@ -1,15 +1,149 @@
-#import bevy_mod_outline::common::{OutlineViewUniform, outline_flat_depth}
+#import bevy_mod_outline::common::{OutlineViewUniform, outline_flat_depth, outline_logical_width, outline_distance_scaled_width, outline_distance_fade}
 
 struct ComposeOutputUniform {
     world_plane_origin: vec3<f32>,
     world_plane_offset: vec3<f32>,
     volume_offset: f32,
     volume_colour: vec4<f32>,
+    occluded_offset: f32,
+    occluded_colour: vec4<f32>,
+    glow_inner: f32,
+    glow_falloff: f32,
+    glow_curve: u32,
+    gradient_mode: u32,
+    gradient_stop_count: u32,
+    gradient_positions: array<vec4<f32>, 2>,
+    gradient_colours: array<vec4<f32>, 8>,
+    width_unit: u32,
+    distance_scaling: vec4<f32>,
+    distance_fade: f32,
+    dash_length: f32,
+    dash_gap: f32,
+    dash_speed: f32,
+    fill_mode: u32,
+    fill_tile_size: vec2<f32>,
+    real_depth: u32,
 }
 
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var<uniform> view: OutlineViewUniform;
 @group(0) @binding(2) var<uniform> instance: ComposeOutputUniform;
+#ifdef SCENE_DEPTH
+#ifdef SCENE_DEPTH_MULTISAMPLED
+@group(0) @binding(3) var scene_depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(3) var scene_depth_texture: texture_depth_2d;
+#endif
+#endif
+@group(1) @binding(0) var gradient_texture: texture_2d<f32>;
+@group(1) @binding(1) var fill_texture: texture_2d<f32>;
+@group(1) @binding(2) var fill_sampler: sampler;
+
+// Curves for the glow falloff, matching `OutlineGlowCurve`.
+const GLOW_LINEAR: u32 = 0u;
+const GLOW_SMOOTH: u32 = 1u;
+
+fn glow_intensity(t: f32) -> f32 {
+    switch instance.glow_curve {
+        case GLOW_LINEAR: {
+            return 1.0 - t;
+        }
+        case GLOW_SMOOTH: {
+            return 1.0 - smoothstep(0.0, 1.0, t);
+        }
+        default: {
+            return (1.0 - t) * (1.0 - t);
+        }
+    }
+}
+
+// Sources for the gradient colour, matching `ComposeOutputUniform`.
+const GRADIENT_NONE: u32 = 0u;
+const GRADIENT_STOPS: u32 = 1u;
+
+fn gradient_colour(t: f32) -> vec4<f32> {
+    if instance.gradient_mode == GRADIENT_STOPS {
+        // Blend towards each stop in turn, so that stops entirely before t
+        // are reached in full and those after it are not reached at all.
+        var colour = instance.gradient_colours[0];
+        for (var i = 1u; i < instance.gradient_stop_count; i++) {
+            let start = instance.gradient_positions[(i - 1u) / 4u][(i - 1u) % 4u];
+            let end = instance.gradient_positions[i / 4u][i % 4u];
+            let s = clamp((t - start) / max(end - start, 1e-5), 0.0, 1.0);
+            colour = mix(colour, instance.gradient_colours[i], s);
+        }
+        return colour;
+    }
+    // Linearly interpolate along the first row of the ramp texture.
+    let width = textureDimensions(gradient_texture).x;
+    let x = t * f32(width - 1u);
+    let i = u32(x);
+    let a = textureLoad(gradient_texture, vec2<u32>(i, 0u), 0);
+    let b = textureLoad(gradient_texture, vec2<u32>(min(i + 1u, width - 1u), 0u), 0);
+    return mix(a, b, fract(x));
+}
+
+fn outline_distance() -> f32 {
+    return length(instance.world_plane_origin - view.world_position);
+}
+
+// Converts a width of this outline to physical pixels, taking widths in world
+// units at the depth of the outline plane and scaling by distance.
+fn physical_width(width: f32) -> f32 {
+    let clip_w = (view.clip_from_world * vec4<f32>(instance.world_plane_origin, 1.0)).w;
+    let logical_width = outline_logical_width(view, width, instance.width_unit, clip_w);
+    return view.scale_physical_from_logical
+        * outline_distance_scaled_width(logical_width, instance.distance_scaling, outline_distance());
+}
+
+const PI: f32 = 3.141592653589793;
+
+// Returns the position along the silhouette of a pixel with the given nearest
+// seed in logical pixels, for a pattern repeating with the given period.
+//
+// There is no parametrisation of the silhouette available to a single pixel,
+// so this approximates one by the angle of the seed around the centre of the
+// outline, scaled by the distance of the seed from the centre. This is exact
+// for circles, and stretches the pattern where the silhouette runs towards or
+// away from the centre. The scale is rounded so that a whole number of periods
+// fit around the centre, leaving no seam where the angle wraps around.
+fn silhouette_position(seed: vec2<f32>, period: f32) -> f32 {
+    let clip = view.clip_from_world * vec4<f32>(instance.world_plane_origin, 1.0);
+    let physical_size = 2.0 * view.scale_physical_from_logical / view.scale_clip_from_logical;
+    let centre = (clip.xy / clip.w * vec2<f32>(0.5, -0.5) + 0.5) * physical_size;
+    let radial = seed - centre;
+    let turns = atan2(radial.y, radial.x) / (2.0 * PI) + 0.5;
+    let circumference = 2.0 * PI * length(radial) / view.scale_physical_from_logical;
+    return turns * max(round(circumference / period), 1.0) * period;
+}
+
+// Returns whether a pixel with the given nearest seed lies in a gap between
+// dashes.
+fn in_dash_gap(seed: vec2<f32>) -> bool {
+    let period = instance.dash_length + instance.dash_gap;
+    let position = silhouette_position(seed, period) + instance.dash_speed * view.time;
+    return position - period * floor(position / period) >= instance.dash_length;
+}
+
+// Mappings of the fill texture, matching `ComposeOutputUniform`.
+const FILL_NONE: u32 = 0u;
+const FILL_SCREEN: u32 = 1u;
+
+// Samples the fill texture for a pixel at the given distance from its nearest
+// seed, where both are in physical pixels.
+fn fill_colour(position: vec2<f32>, seed: vec2<f32>, dist: f32) -> vec4<f32> {
+    var uv: vec2<f32>;
+    if instance.fill_mode == FILL_SCREEN {
+        uv = position / view.scale_physical_from_logical;
+    } else {
+        uv = vec2<f32>(
+            silhouette_position(seed, instance.fill_tile_size.x),
+            dist / view.scale_physical_from_logical,
+        );
+    }
+    // Sampled without implicit derivatives, as this follows discards.
+    return textureSampleLevel(fill_texture, fill_sampler, uv / instance.fill_tile_size, 0.0);
+}
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -36,25 +170,67 @@ fn vertex(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
 @fragment
 fn fragment(in: VertexOutput) -> FragmentOutput {
     let tex = textureLoad(screen_texture, vec2<i32>(in.position.xy), 0);
-    let threshold = view.scale_physical_from_logical * instance.volume_offset;
+    var out: FragmentOutput;
+    if instance.real_depth != 0u {
+        // Take the depth of the nearest seed, as carried by the flood.
+        out.frag_depth = tex.z;
+    } else {
+        out.frag_depth = in.flat_depth;
+    }
+    var colour = instance.volume_colour;
+    var offset = instance.volume_offset;
+    var glow = instance.glow_falloff > 0.0;
+    var gradient = instance.gradient_mode != GRADIENT_NONE;
+    var dashed = instance.dash_length > 0.0;
+    var filled = instance.fill_mode != FILL_NONE;
+#ifdef SCENE_DEPTH
+    let scene_depth = textureLoad(scene_depth_texture, vec2<i32>(in.position.xy), 0);
+    if out.frag_depth < scene_depth {
+        // Occluded by the scene, so use the occluded style. This is fully
+        // transparent if the outline has no occluded style.
+        colour = instance.occluded_colour;
+        offset = instance.occluded_offset;
+        glow = false;
+        gradient = false;
+        dashed = false;
+        if colour.a <= 0.0 {
+            discard;
+        }
+    }
+#endif
     // The flood texture stores the delta from each pixel to its nearest seed,
     // so the distance to that seed is the length of the stored delta.
     let dist = length(tex.xy);
-    var out: FragmentOutput;
-#ifdef MSAA
-    let inner = max(threshold - 1.0, 0.0);
-    let coverage = 1.0 - smoothstep(inner, threshold, dist);
-    if coverage <= 0.0 {
+    if dashed && in_dash_gap(in.position.xy + tex.xy) {
         discard;
     }
-    out.colour = vec4<f32>(instance.volume_colour.rgb, instance.volume_colour.a * coverage);
-#else
-    if dist <= threshold {
-        out.colour = instance.volume_colour;
+    if gradient {
+        // The volume offset spans the full width of the volume, including
+        // any glow.
+        let extent = physical_width(instance.volume_offset);
+        colour = gradient_colour(clamp(dist / max(extent, 1e-5), 0.0, 1.0));
+    }
+    if filled {
+        colour *= fill_colour(in.position.xy, in.position.xy + tex.xy, dist);
+    }
+    var coverage: f32;
+    if glow {
+        let inner = physical_width(instance.glow_inner);
+        let falloff = physical_width(instance.glow_falloff);
+        coverage = glow_intensity(clamp((dist - inner) / falloff, 0.0, 1.0));
     } else {
+        let threshold = physical_width(offset);
+#ifdef MSAA
+        let inner = max(threshold - 1.0, 0.0);
+        coverage = 1.0 - smoothstep(inner, threshold, dist);
+#else
+        coverage = select(0.0, 1.0, dist <= threshold);
+#endif
+    }
+    coverage *= outline_distance_fade(instance.distance_scaling, instance.distance_fade, outline_distance());
+    if coverage <= 0.0 {
         discard;
     }
-#endif
-    out.frag_depth = in.flat_depth;
+    out.colour = vec4<f32>(colour.rgb, colour.a * coverage);
     return out;
 }
@@ -23,7 +23,7 @@ use crate::uniforms::RenderOutlineInstances;
 use crate::view_uniforms::OutlineQueueStatus;
 
 use super::node::FloodOutline;
-use super::{DrawMode, DrawOutline, OutlineViewUniform, FLOOD_OPS};
+use super::{DrawMode, DrawOutline, DrawOutline2d, OutlineViewUniform, FLOOD_OPS};
 
 pub(crate) fn prepare_flood_phases(
     query: Query<&ExtractedView, With<OutlineViewUniform>>,
@@ -47,6 +47,10 @@ pub(crate) fn queue_flood_meshes(
     mut views: Query<(&ExtractedView, &mut OutlineQueueStatus)>,
 ) {
     let draw_flood = flood_draw_functions.read().get_id::<DrawOutline>().unwrap();
+    let draw_flood_2d = flood_draw_functions
+        .read()
+        .get_id::<DrawOutline2d>()
+        .unwrap();
 
     for (view, mut queue_status) in views.iter_mut() {
         let Some(flood_phase) = flood_phases.get_mut(&view.retained_view_entity) else {
@@ -89,8 +93,7 @@ pub(crate) fn queue_flood_meshes(
             let index_slab = mesh_slabs.and_then(|s| s.index_slab_id);
 
             let Some(OutlineCacheEntry {
-                stencil_pipeline_id: _,
-                volume_pipeline_id,
+                volume_pipeline_id, ..
             }) = outline_view_cache.entity_map.get(&main_entity)
             else {
                 continue;
@@ -103,15 +106,37 @@ pub(crate) fn queue_flood_meshes(
             flood_phase.add_retained(FloodOutline {
                 sorting_info,
                 distance: 0.0,
+                group_distance: 0.0,
                 entity: Entity::PLACEHOLDER,
                 main_entity,
                 pipeline: *volume_pipeline_id,
-                draw_function: draw_flood,
+                draw_function: if outline.pipeline_key.mesh_2d() {
+                    draw_flood_2d
+                } else {
+                    draw_flood
+                },
                 batch_range: 0..0,
                 extra_index: PhaseItemExtraIndex::None,
                 indexed: index_slab.is_some(),
                 volume_offset: outline.instance_data.volume_offset,
                 volume_colour: outline.instance_data.volume_colour,
+                occluded_offset: if outline.occluded {
+                    outline.instance_data.occluded_offset
+                } else {
+                    0.0
+                },
+                occluded_colour: outline.instance_data.occluded_colour,
+                rings: outline.rings.clone(),
+                glow: outline.glow,
+                gradient: outline.gradient.clone(),
+                dashes: outline.dashes,
+                fill_texture_id: outline.fill_texture_id,
+                fill_mapping: outline.fill_mapping,
+                fill_tile_size: outline.instance_data.fill_tile_size,
+                width_unit: outline.width_unit,
+                distance_scaling: outline.distance_scaling.clone(),
+                depth_mode: outline.depth_mode,
+                group: outline.group,
             });
         }
 
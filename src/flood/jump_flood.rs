@@ -61,7 +61,7 @@ pub(crate) fn init_jump_flood_pipeline(
             shader_defs: vec![],
             entry_point: None,
             targets: vec![Some(ColorTargetState {
-                format: TextureFormat::Rg16Float,
+                format: TextureFormat::Rgba16Float,
                 blend: None,
                 write_mask: ColorWrites::ALL,
             })],
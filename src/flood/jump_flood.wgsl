@@ -14,8 +14,8 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     let step = i32(instance.step_length);
     let pos = vec2<i32>(in.position.xy);
 
-    var result = textureLoad(screen_texture, pos, 0).xy;
-    var closest_dist = length(result);
+    var result = textureLoad(screen_texture, pos, 0).xyz;
+    var closest_dist = length(result.xy);
 
     // Check all 8 neighbouring pixels at the current step distance.
     for (var dy = -1; dy <= 1; dy++) {
@@ -30,14 +30,16 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
                 continue;
             }
 
-            let delta = textureLoad(screen_texture, neighbour_coord, 0).xy + vec2<f32>(offset);
+            let neighbour = textureLoad(screen_texture, neighbour_coord, 0).xyz;
+            let delta = neighbour.xy + vec2<f32>(offset);
             let dist = length(delta);
 
+            // Carry the seed depth along with the delta to its seed.
             if (dist < closest_dist) {
                 closest_dist = dist;
-                result = delta;
+                result = vec3<f32>(delta, neighbour.z);
             }
         }
     }
-    return vec4<f32>(result, 0.0, 0.0);
+    return vec4<f32>(result, 0.0);
 }
@@ -1,5 +1,5 @@
 use bevy::asset::{load_internal_asset, uuid_handle};
-use bevy::core_pipeline::{Core3d, Core3dSystems};
+use bevy::core_pipeline::{Core2d, Core3d};
 use bevy::pbr::{MeshInputUniform, MeshUniform};
 use bevy::render::batching::gpu_preprocessing::{BatchedInstanceBuffers, GpuPreprocessingSupport};
 use bevy::render::render_phase::{
@@ -10,17 +10,19 @@ use bevy::{
     prelude::*,
     render::{
         camera::ExtractedCamera,
+        init_gpu_resource,
         render_resource::{
             Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
         },
         renderer::RenderDevice,
-        texture::{CachedTexture, TextureCache},
+        texture::{CachedTexture, FallbackImage, TextureCache},
         Render, RenderApp, RenderStartup, RenderSystems,
     },
 };
 use compose_output::{
-    init_compose_output_pipeline, prepare_compose_output_pass, prepare_compose_output_uniform,
-    ComposeOutputUniforms,
+    init_compose_output_pipeline, init_compose_output_texture_bind_groups,
+    prepare_compose_output_pass, prepare_compose_output_texture_bind_groups,
+    prepare_compose_output_uniform, ComposeOutputUniforms,
 };
 use flood_init::{prepare_flood_phases, queue_flood_meshes};
 use jump_flood::init_jump_flood_pipeline;
@@ -30,9 +32,9 @@ use wgpu_types::{LoadOp, Operations, StoreOp};
 
 use crate::add_dummy_phase_buffer;
 use crate::msaa::ResolvedOutlineMsaa;
-use crate::node::outline_render_pass;
 use crate::pipeline::OutlinePipeline;
-use crate::render::DrawOutline;
+use crate::placement::{OutlinePassSystems, PLACEMENTS};
+use crate::render::{DrawOutline, DrawOutline2d};
 use crate::uniforms::DrawMode;
 use crate::view_uniforms::OutlineViewUniform;
 
@@ -118,14 +120,14 @@ pub fn prepare_flood_textures(
             mip_level_count: 1,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Rg16Float,
+            format: TextureFormat::Rgba16Float,
             usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
         };
 
         let coverage = if msaa.samples() > 1 {
             let coverage_descriptor = TextureDescriptor {
-                format: TextureFormat::R8Unorm,
+                format: TextureFormat::Rg16Float,
                 ..texture_descriptor.clone()
             };
             let msaa_tex = texture_cache.get(
@@ -195,6 +197,7 @@ impl Plugin for FloodPlugin {
         .init_resource::<ComposeOutputUniforms>()
         .init_resource::<DrawFunctions<FloodOutline>>()
         .add_render_command::<FloodOutline, DrawOutline>()
+        .add_render_command::<FloodOutline, DrawOutline2d>()
         .add_systems(
             Render,
             prepare_flood_phases
@@ -205,17 +208,28 @@ impl Plugin for FloodPlugin {
             Render,
             (prepare_flood_textures, prepare_compose_output_uniform, prepare_compose_output_pass).in_set(RenderSystems::Prepare),
         )
-        .add_systems(Render, queue_flood_meshes.in_set(RenderSystems::QueueMeshes))
         .add_systems(
             Render,
-            sort_phase_system::<FloodOutline>.in_set(RenderSystems::PhaseSort),
+            prepare_compose_output_texture_bind_groups.in_set(RenderSystems::PrepareBindGroups),
         )
+        .add_systems(Render, queue_flood_meshes.in_set(RenderSystems::QueueMeshes))
         .add_systems(
-            Core3d,
-            flood_render_pass
-                .after(outline_render_pass)
-                .in_set(Core3dSystems::PostProcess),
+            Render,
+            sort_phase_system::<FloodOutline>.in_set(RenderSystems::PhaseSort),
         );
+
+        let render_app = app.sub_app_mut(RenderApp);
+        for placement in PLACEMENTS {
+            render_app
+                .add_systems(
+                    Core3d,
+                    flood_render_pass.in_set(OutlinePassSystems::Flood(placement)),
+                )
+                .add_systems(
+                    Core2d,
+                    flood_render_pass.in_set(OutlinePassSystems::Flood(placement)),
+                );
+        }
     }
 
     fn finish(&self, app: &mut App) {
@@ -226,6 +240,9 @@ impl Plugin for FloodPlugin {
                 init_sobel_init_pipeline,
                 init_jump_flood_pipeline,
                 init_compose_output_pipeline,
+                init_compose_output_texture_bind_groups
+                    .after(init_compose_output_pipeline)
+                    .after(init_gpu_resource::<FallbackImage>),
             ),
         );
 
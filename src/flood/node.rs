@@ -1,4 +1,5 @@
 use bevy::ecs::entity::EntityHash;
+use bevy::platform::collections::HashMap;
 use bevy::render::render_phase::{
     CachedRenderPipelinePhaseItem, DrawFunctionId, PhaseItem, ViewSortedRenderPhases,
 };
@@ -22,7 +23,10 @@ use std::ops::Range;
 use crate::culling::RenderExtractedOutlineEntities;
 use crate::msaa::{OutlineViewTextures, ResolvedOutlineMsaa};
 use crate::node::{OutlineRangefinder, OutlineSortingInfo};
-use crate::OutlineViewUniform;
+use crate::uniforms::{
+    DepthMode, ExtractedDashes, ExtractedGlow, ExtractedGradient, ExtractedRing,
+};
+use crate::{OutlineDistanceScaling, OutlineTextureMapping, OutlineViewUniform, OutlineWidthUnit};
 
 use super::compose_output::{ComposeOutputPass, ComposeOutputView};
 use super::flood_init::FloodInitPass;
@@ -34,6 +38,9 @@ use super::FloodTextures;
 pub struct FloodOutline {
     pub sorting_info: OutlineSortingInfo,
     pub distance: f32,
+    /// Distance of the nearest member of the item's group, or of the item
+    /// itself if ungrouped.
+    pub group_distance: f32,
     pub entity: Entity,
     pub main_entity: MainEntity,
     pub pipeline: CachedRenderPipelineId,
@@ -43,6 +50,65 @@ pub struct FloodOutline {
     pub indexed: bool,
     pub volume_offset: f32,
     pub volume_colour: Vec4,
+    pub occluded_offset: f32,
+    pub occluded_colour: Vec4,
+    pub rings: Vec<ExtractedRing>,
+    pub glow: Option<ExtractedGlow>,
+    pub gradient: Option<ExtractedGradient>,
+    pub dashes: Option<ExtractedDashes>,
+    pub fill_texture_id: Option<AssetId<Image>>,
+    pub fill_mapping: OutlineTextureMapping,
+    pub fill_tile_size: Vec2,
+    pub width_unit: OutlineWidthUnit,
+    pub distance_scaling: OutlineDistanceScaling,
+    pub depth_mode: DepthMode,
+    pub group: Option<u32>,
+}
+
+impl FloodOutline {
+    /// Returns the properties which must match for ungrouped items to be
+    /// merged into one silhouette.
+    #[allow(clippy::type_complexity)]
+    fn style_key(
+        &self,
+    ) -> (
+        f32,
+        f32,
+        Vec<ExtractedRing>,
+        OutlineWidthUnit,
+        OutlineDistanceScaling,
+        // Styling which only affects composing the output
+        (
+            Vec4,
+            Vec4,
+            Option<ExtractedGlow>,
+            Option<ExtractedGradient>,
+            Option<ExtractedDashes>,
+            Option<AssetId<Image>>,
+            OutlineTextureMapping,
+            Vec2,
+            DepthMode,
+        ),
+    ) {
+        (
+            self.volume_offset,
+            self.occluded_offset,
+            self.rings.clone(),
+            self.width_unit,
+            self.distance_scaling.clone(),
+            (
+                self.volume_colour,
+                self.occluded_colour,
+                self.glow,
+                self.gradient.clone(),
+                self.dashes,
+                self.fill_texture_id,
+                self.fill_mapping,
+                self.fill_tile_size,
+                self.depth_mode,
+            ),
+        )
+    }
 }
 
 impl PhaseItem for FloodOutline {
@@ -89,10 +155,16 @@ impl CachedRenderPipelinePhaseItem for FloodOutline {
 }
 
 impl SortedPhaseItem for FloodOutline {
-    type SortKey = FloatOrd;
+    type SortKey = (FloatOrd, Option<u32>, FloatOrd);
 
+    // Members of a group are contiguous, sorted by the nearest member and
+    // starting with it so that it provides the style and depth of the group.
     fn sort_key(&self) -> Self::SortKey {
-        FloatOrd(self.distance)
+        (
+            FloatOrd(self.group_distance),
+            self.group,
+            FloatOrd(-self.distance),
+        )
     }
 
     fn recalculate_sort_keys(
@@ -100,8 +172,18 @@ impl SortedPhaseItem for FloodOutline {
         view: &ExtractedView,
     ) {
         let rangefinder = OutlineRangefinder::new(view);
+        let mut group_distances = HashMap::<u32, f32>::new();
         for item in items.values_mut() {
             item.distance = rangefinder.distance_of(&item.sorting_info);
+            if let Some(group) = item.group {
+                let group_distance = group_distances.entry(group).or_insert(item.distance);
+                *group_distance = group_distance.max(item.distance);
+            }
+        }
+        for item in items.values_mut() {
+            item.group_distance = item
+                .group
+                .map_or(item.distance, |group| group_distances[&group]);
         }
     }
 
@@ -172,16 +254,32 @@ pub(crate) fn flood_render_pass(
         return;
     };
 
-    for ((_, volume_offset, _), group) in &flood_phase
+    // Explicit groups are merged into one silhouette, while other items are
+    // merged only where they lie at the same distance with the same style
+    for (_, group) in &flood_phase
         .items
         .values()
         .enumerate()
-        .chunk_by(|(_, item)| (item.distance, item.volume_offset, item.volume_colour))
+        .chunk_by(|(_, item)| {
+            (
+                item.group_distance,
+                item.group,
+                item.group.is_none().then(|| item.style_key()),
+            )
+        })
     {
         let mut group_iter = group.into_iter();
         let Some((first_index, first_item)) = group_iter.next() else {
             continue;
         };
+        let &FloodOutline {
+            volume_offset,
+            occluded_offset,
+            ref rings,
+            width_unit,
+            ref distance_scaling,
+            ..
+        } = first_item;
 
         // Sum item range and screen-space bounds
         let mut last_index = first_index;
@@ -226,7 +324,29 @@ pub(crate) fn flood_render_pass(
         }
         flood_textures.flip();
 
-        let scaled_offset = view_uniform.scale_physical_from_logical * volume_offset;
+        // The occluded part of the outline and any rings may be wider than
+        // the volume, so flood far enough for the widest of them
+        let max_offset = rings
+            .iter()
+            .fold(volume_offset.max(occluded_offset), |offset, ring| {
+                offset.max(ring.offset)
+            });
+        let clip_w = view_uniform
+            .clip_from_world
+            .mul_vec4(first_item.sorting_info.world_plane_origin.extend(1.0))
+            .w;
+        let logical_offset = width_unit.to_logical(
+            max_offset,
+            view_uniform.scale_physical_from_logical,
+            view_uniform.scale_logical_from_world,
+            clip_w,
+        );
+        let distance = first_item
+            .sorting_info
+            .world_plane_origin
+            .distance(view_uniform.world_position);
+        let scaled_offset = view_uniform.scale_physical_from_logical
+            * distance_scaling.scale_width(logical_offset, distance);
         let passes = if scaled_offset > 0.0 {
             (scaled_offset.ceil() as u32 / 2 + 1)
                 .next_power_of_two()
@@ -248,13 +368,18 @@ pub(crate) fn flood_render_pass(
             flood_textures.flip();
         }
 
-        compose_output_pass.execute(
-            &mut render_context,
-            view_entity,
-            first_item.main_entity,
-            flood_textures.input(),
-            &pipeline_cache,
-            &screen_space_bounds,
-        );
+        // Compose the volume followed by each ring from the same distance
+        // field, so that each is drawn outside of those before it
+        for ring in 0..=rings.len() {
+            compose_output_pass.execute(
+                &mut render_context,
+                view_entity,
+                first_item.main_entity,
+                ring,
+                flood_textures.input(),
+                &pipeline_cache,
+                &screen_space_bounds,
+            );
+        }
     }
 }
@@ -48,7 +48,7 @@ pub(crate) fn init_sobel_init_pipeline(
             shader_defs: vec![],
             entry_point: None,
             targets: vec![Some(ColorTargetState {
-                format: TextureFormat::Rg16Float,
+                format: TextureFormat::Rgba16Float,
                 blend: None,
                 write_mask: ColorWrites::ALL,
             })],
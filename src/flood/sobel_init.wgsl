@@ -9,12 +9,16 @@ fn sample(p: vec2<i32>) -> f32 {
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     let pos = vec2<i32>(in.position.xy);
-    let coverage = sample(pos);
+    let centre = textureLoad(coverage_texture, pos, 0).rg;
+    let coverage = centre.r;
 
     if coverage <= 0.0 {
         discard;
     }
 
+    // The resolved depth is premultiplied by coverage.
+    let depth = centre.g / coverage;
+
     // 3x3 neighbourhood coverage values for a Sobel gradient.
     let tl = sample(pos + vec2<i32>(-1, -1));
     let tc = sample(pos + vec2<i32>( 0, -1));
@@ -39,5 +43,5 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
         delta = outward * (coverage - 0.5);
     }
 
-    return vec4<f32>(delta, 0.0, 0.0);
+    return vec4<f32>(delta, depth, 0.0);
 }
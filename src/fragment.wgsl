@@ -1,4 +1,5 @@
 #import bevy_mod_outline::common::VertexOutput
+#import bevy_mod_outline::scene_depth::outline_scene_depth_test
 
 struct FragmentOutput {
     @location(0) colour: vec4<f32>,
@@ -12,10 +13,20 @@ struct FragmentOutput {
 @group(3) @binding(1) var alpha_mask_sampler: sampler;
 #endif
 
+#ifdef FILL_TEXTURE
+@group(3) @binding(0) var fill_texture: texture_2d<f32>;
+@group(3) @binding(1) var fill_sampler: sampler;
+#endif
+
 @fragment
 fn fragment(vertex: VertexOutput) -> FragmentOutput {
     var out: FragmentOutput;
 
+#ifdef FILL_TEXTURE
+    // Sampled before any discards, which must not precede implicit derivatives.
+    let fill = textureSample(fill_texture, fill_sampler, vertex.position.xy * vertex.fill_scale);
+#endif
+
 #ifdef ALPHA_MASK_TEXTURE
     let alpha_mask = textureSample(alpha_mask_texture, alpha_mask_sampler, vertex.uv)[#{ALPHA_MASK_CHANNEL}];
     if (alpha_mask < vertex.alpha_mask_threshold) {
@@ -26,14 +37,26 @@ fn fragment(vertex: VertexOutput) -> FragmentOutput {
 #ifdef FLAT_DEPTH
     out.frag_depth = vertex.flat_depth;
 #endif
+    if (!outline_scene_depth_test(vertex)) {
+        discard;
+    }
 #ifdef VOLUME
+    // Outlines which have faded out with distance must not occlude others.
+    if (vertex.volume_colour.a <= 0.0) {
+        discard;
+    }
     out.colour = vertex.volume_colour;
+#ifdef FILL_TEXTURE
+    out.colour *= fill;
+#endif
 #endif
 #ifdef FLOOD_INIT
+    // The seed depth is carried alongside the flood delta for real depth
+    // outlines. Under MSAA it is premultiplied by coverage when resolved.
 #ifdef MSAA
-    out.colour = vec4<f32>(1.0, 0.0, 0.0, 0.0);
+    out.colour = vec4<f32>(1.0, vertex.position.z, 0.0, 0.0);
 #else
-    out.colour = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    out.colour = vec4<f32>(0.0, 0.0, vertex.position.z, 0.0);
 #endif
 #endif
     return out;
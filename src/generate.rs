@@ -1,3 +1,5 @@
+use std::f32::consts::FRAC_PI_4;
+
 use bevy::{
     asset::RenderAssetUsages,
     math::FloatOrd,
@@ -7,7 +9,7 @@ use bevy::{
     render::render_resource::{PrimitiveTopology, VertexFormat},
 };
 
-use crate::ATTRIBUTE_OUTLINE_NORMAL;
+use crate::{TextureChannel, ATTRIBUTE_OUTLINE_NORMAL, ATTRIBUTE_OUTLINE_WIDTH};
 
 enum IndexIterator<'a> {
     ExplicitU16(std::slice::Iter<'a, u16>),
@@ -93,6 +95,30 @@ pub enum GenerateOutlineNormalsError {
     InvalidVertexAttributeFormat(&'static str, VertexFormat, VertexFormat),
 }
 
+/// Source from which outline widths are derived.
+#[derive(Copy, Clone)]
+#[non_exhaustive]
+pub enum GenerateOutlineWidthsFrom {
+    /// Interpolate between two widths according to the curvature of the
+    /// surface at each vertex, from `flat` where the adjoining faces are
+    /// coplanar to `sharp` where they meet at a right angle or sharper, such
+    /// as along the edges and at the corners of a cube.
+    Curvature { flat: f32, sharp: f32 },
+    /// Use a channel of the vertex colours provided by the mesh.
+    VertexColour(TextureChannel),
+}
+
+/// Failed to generate outline widths for the mesh.
+#[derive(thiserror::Error, Debug)]
+pub enum GenerateOutlineWidthsError {
+    #[error("unsupported primitive topology '{0:?}'")]
+    UnsupportedPrimitiveTopology(PrimitiveTopology),
+    #[error("missing vertex attributes '{0}'")]
+    MissingVertexAttribute(&'static str),
+    #[error("the '{0}' vertex attribute should have {1:?} format, but had {2:?} format")]
+    InvalidVertexAttributeFormat(&'static str, VertexFormat, VertexFormat),
+}
+
 /// Extension methods for [`Mesh`].
 pub trait OutlineMeshExt: Sized {
     /// Generates outline normals for the mesh.
@@ -116,6 +142,26 @@ pub trait OutlineMeshExt: Sized {
         self,
         settings: &GenerateOutlineNormalsSettings,
     ) -> Result<Self, GenerateOutlineNormalsError>;
+
+    /// Generates outline widths for the mesh.
+    ///
+    /// The widths are inserted as the [`ATTRIBUTE_OUTLINE_WIDTH`] vertex
+    /// attribute, which scales the width of the outline at each vertex when
+    /// rendering with vertex extrusion. This can be used to taper outlines for
+    /// an inked look.
+    ///
+    /// Generating from curvature only supports meshes with TriangleList
+    /// topology.
+    fn generate_outline_widths(
+        &mut self,
+        from: GenerateOutlineWidthsFrom,
+    ) -> Result<(), GenerateOutlineWidthsError>;
+
+    /// Chainable version of [`generate_outline_widths`](OutlineMeshExt::generate_outline_widths).
+    fn with_generated_outline_widths(
+        self,
+        from: GenerateOutlineWidthsFrom,
+    ) -> Result<Self, GenerateOutlineWidthsError>;
 }
 
 impl OutlineMeshExt for Mesh {
@@ -197,6 +243,121 @@ impl OutlineMeshExt for Mesh {
     ) -> Result<Self, GenerateOutlineNormalsError> {
         self.generate_outline_normals(settings).map(|_| self)
     }
+
+    fn generate_outline_widths(
+        &mut self,
+        from: GenerateOutlineWidthsFrom,
+    ) -> Result<(), GenerateOutlineWidthsError> {
+        let widths = match from {
+            GenerateOutlineWidthsFrom::Curvature { flat, sharp } => {
+                curvature_widths(self, flat, sharp)?
+            }
+            GenerateOutlineWidthsFrom::VertexColour(channel) => {
+                let colours = match self.attribute(Mesh::ATTRIBUTE_COLOR).ok_or(
+                    GenerateOutlineWidthsError::MissingVertexAttribute(Mesh::ATTRIBUTE_COLOR.name),
+                )? {
+                    VertexAttributeValues::Float32x4(c) => Ok(c),
+                    v => Err(GenerateOutlineWidthsError::InvalidVertexAttributeFormat(
+                        Mesh::ATTRIBUTE_COLOR.name,
+                        VertexFormat::Float32x4,
+                        v.into(),
+                    )),
+                }?;
+                let index = match channel {
+                    TextureChannel::R => 0,
+                    TextureChannel::G => 1,
+                    TextureChannel::B => 2,
+                    TextureChannel::A => 3,
+                };
+                colours.iter().map(|c| c[index]).collect()
+            }
+        };
+        self.insert_attribute(
+            ATTRIBUTE_OUTLINE_WIDTH,
+            VertexAttributeValues::Float32(widths),
+        );
+        Ok(())
+    }
+
+    fn with_generated_outline_widths(
+        mut self,
+        from: GenerateOutlineWidthsFrom,
+    ) -> Result<Self, GenerateOutlineWidthsError> {
+        self.generate_outline_widths(from).map(|_| self)
+    }
+}
+
+/// Derives a width for each vertex from the mean angle between its adjoining
+/// face normals and their average, weighted by the angle of each face at the
+/// vertex and grouping vertices by position. Two faces meeting at a right
+/// angle each lie 45 degrees from their average, which is taken as fully sharp.
+fn curvature_widths(
+    mesh: &Mesh,
+    flat: f32,
+    sharp: f32,
+) -> Result<Vec<f32>, GenerateOutlineWidthsError> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return Err(GenerateOutlineWidthsError::UnsupportedPrimitiveTopology(
+            mesh.primitive_topology(),
+        ));
+    }
+    let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION).ok_or(
+        GenerateOutlineWidthsError::MissingVertexAttribute(Mesh::ATTRIBUTE_POSITION.name),
+    )? {
+        VertexAttributeValues::Float32x3(p) => Ok(p),
+        v => Err(GenerateOutlineWidthsError::InvalidVertexAttributeFormat(
+            Mesh::ATTRIBUTE_POSITION.name,
+            VertexFormat::Float32x3,
+            v.into(),
+        )),
+    }?;
+    // Collect the face normals and angles adjoining each position
+    let mut map = HashMap::<[FloatOrd; 3], Vec<(f32, Vec3)>>::with_capacity(positions.len());
+    let mut it = IndexIterator::from(mesh);
+    while let (Some(i0), Some(i1), Some(i2)) = (it.next(), it.next(), it.next()) {
+        for (j0, j1, j2) in [(i0, i1, i2), (i1, i2, i0), (i2, i0, i1)] {
+            let p0 = Vec3::from(positions[j0]);
+            let p1 = Vec3::from(positions[j1]);
+            let p2 = Vec3::from(positions[j2]);
+            let angle = (p1 - p0).angle_between(p2 - p0);
+            let Some(face_normal) = (p1 - p0).cross(p2 - p0).try_normalize() else {
+                continue; // Degenerate triangle
+            };
+            map.entry([FloatOrd(p0.x), FloatOrd(p0.y), FloatOrd(p0.z)])
+                .or_default()
+                .push((angle, face_normal));
+        }
+    }
+    let curvatures = map
+        .into_iter()
+        .map(|(key, faces)| {
+            let average = faces
+                .iter()
+                .fold(Vec3::ZERO, |sum, (angle, normal)| sum + angle * normal)
+                .normalize_or_zero();
+            let total_angle = faces.iter().map(|(angle, _)| angle).sum::<f32>();
+            let curvature = if total_angle <= 0.0 {
+                0.0
+            } else if average == Vec3::ZERO {
+                1.0 // Faces folded back onto each other
+            } else {
+                let deviation = faces
+                    .iter()
+                    .map(|(angle, normal)| angle * normal.angle_between(average))
+                    .sum::<f32>();
+                (deviation / total_angle / FRAC_PI_4).clamp(0.0, 1.0)
+            };
+            (key, curvature)
+        })
+        .collect::<HashMap<_, _>>();
+    Ok(positions
+        .iter()
+        .map(|p| {
+            let key = [FloatOrd(p[0]), FloatOrd(p[1]), FloatOrd(p[2])];
+            let curvature = curvatures.get(&key).copied().unwrap_or(0.0);
+            flat.lerp(sharp, curvature)
+        })
+        .collect())
 }
 
 fn auto_generate_outline_normals(
@@ -249,3 +410,144 @@ impl Plugin for AutoGenerateOutlineNormalsPlugin {
             .add_systems(Update, auto_generate_outline_normals);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::mesh::MeshVertexAttribute;
+
+    use super::*;
+
+    fn outline_widths(mesh: &Mesh) -> &[f32] {
+        match mesh.attribute(ATTRIBUTE_OUTLINE_WIDTH) {
+            Some(VertexAttributeValues::Float32(widths)) => widths,
+            _ => panic!("Mesh should have outline widths"),
+        }
+    }
+
+    fn assert_widths(mesh: &Mesh, expected: &[f32]) {
+        let widths = outline_widths(mesh);
+        assert_eq!(widths.len(), expected.len());
+        for (width, expected) in widths.iter().zip(expected) {
+            assert!(
+                (width - expected).abs() < 1e-4,
+                "Expected widths {expected:?}, got {widths:?}"
+            );
+        }
+    }
+
+    const CURVATURE: GenerateOutlineWidthsFrom = GenerateOutlineWidthsFrom::Curvature {
+        flat: 1.0,
+        sharp: 3.0,
+    };
+
+    #[test]
+    fn test_curvature_flat() {
+        let mesh = Mesh::from(Plane3d::default().mesh().subdivisions(2))
+            .with_generated_outline_widths(CURVATURE)
+            .unwrap();
+        let count = mesh.count_vertices();
+        assert_widths(&mesh, &vec![1.0; count]);
+    }
+
+    #[test]
+    fn test_curvature_cube() {
+        // Every vertex of a cube lies on a corner.
+        let mesh = Mesh::from(Cuboid::default())
+            .with_generated_outline_widths(CURVATURE)
+            .unwrap();
+        let count = mesh.count_vertices();
+        assert_widths(&mesh, &vec![3.0; count]);
+    }
+
+    #[test]
+    fn test_curvature_right_angle() {
+        // Two quads meeting at a right angle along the edge from the origin
+        // to (1, 0, 0), with the other vertices lying on free edges.
+        let mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, 1.0],
+                [0.0, 0.0, 1.0],
+                [1.0, -1.0, 0.0],
+                [0.0, -1.0, 0.0],
+            ],
+        )
+        .with_inserted_indices(Indices::U32(vec![0, 3, 2, 0, 2, 1, 0, 1, 4, 0, 4, 5]))
+        .with_generated_outline_widths(CURVATURE)
+        .unwrap();
+        assert_widths(&mesh, &[3.0, 3.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_curvature_unsupported_topology() {
+        let mut mesh = Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; 2]);
+        assert!(matches!(
+            mesh.generate_outline_widths(CURVATURE),
+            Err(GenerateOutlineWidthsError::UnsupportedPrimitiveTopology(
+                PrimitiveTopology::LineList
+            ))
+        ));
+    }
+
+    fn coloured_mesh() -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; 2])
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_COLOR,
+            vec![[0.1, 0.2, 0.3, 0.4], [0.5, 0.6, 0.7, 0.8]],
+        )
+    }
+
+    #[test]
+    fn test_vertex_colour_channels() {
+        for (channel, expected) in [
+            (TextureChannel::R, [0.1, 0.5]),
+            (TextureChannel::G, [0.2, 0.6]),
+            (TextureChannel::B, [0.3, 0.7]),
+            (TextureChannel::A, [0.4, 0.8]),
+        ] {
+            let mesh = coloured_mesh()
+                .with_generated_outline_widths(GenerateOutlineWidthsFrom::VertexColour(channel))
+                .unwrap();
+            assert_eq!(outline_widths(&mesh), expected);
+        }
+    }
+
+    #[test]
+    fn test_vertex_colour_errors() {
+        let from = GenerateOutlineWidthsFrom::VertexColour(TextureChannel::R);
+
+        let mut mesh = coloured_mesh();
+        mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+        assert!(matches!(
+            mesh.generate_outline_widths(from),
+            Err(GenerateOutlineWidthsError::MissingVertexAttribute(_))
+        ));
+
+        // Colours with the wrong format under the same attribute
+        let rgb_colour = MeshVertexAttribute {
+            format: VertexFormat::Float32x3,
+            ..Mesh::ATTRIBUTE_COLOR
+        };
+        mesh.insert_attribute(rgb_colour, vec![[0.0f32; 3]; 2]);
+        assert!(matches!(
+            mesh.generate_outline_widths(from),
+            Err(GenerateOutlineWidthsError::InvalidVertexAttributeFormat(
+                _,
+                VertexFormat::Float32x4,
+                VertexFormat::Float32x3
+            ))
+        ));
+        assert!(mesh.attribute(ATTRIBUTE_OUTLINE_WIDTH).is_none());
+    }
+}
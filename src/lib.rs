@@ -4,7 +4,11 @@
 //!
 //! Outlines are rendered in a seperate pass following the main 3D pass and
 //! using a separate depth buffer. This ensures that outlines are not clipped
-//! by non-outline geometry.
+//! by non-outline geometry. The pass is placed after tone-mapping by default,
+//! which can be changed for each camera using [`OutlinePassPlacement`].
+//! Adding [`OutlineSceneOcclusion`] to a camera allows outlines to instead be
+//! hidden by the rest of the scene, and [`OutlineRenderTarget`] redirects them
+//! into an image for use as a mask.
 //!
 //! An outline consists of two parts, a volume and a stencil. The volume
 //! will, by itself, cover the original object entirely with the outline
@@ -33,19 +37,66 @@
 //! fidelity of meshes and requires smooth outline normals to avoid visual
 //! artefacts. See the [`OutlineMeshExt::generate_outline_normals`] function
 //! and the [`AutoGenerateOutlineNormalsPlugin`] for help generating these.
+//! The width of extruded outlines may also be varied across a mesh using the
+//! [`ATTRIBUTE_OUTLINE_WIDTH`] vertex attribute, which can be generated by
+//! [`OutlineMeshExt::generate_outline_widths`]. Likewise, the colour of
+//! extruded outlines may be varied using [`ATTRIBUTE_OUTLINE_COLOUR`].
 //!
 //! Jump flood, as a screen-space technique, is more robust especially with
 //! thicker outlines. However, it requires `log2(n)` shader passes over the
 //! outline's bounding box where `n` is the thickness in screen pixels.
+//!
+//! Outlines may also be applied to 2D meshes ([`Mesh2d`]) viewed through a
+//! [`Camera2d`]. As the normals of a flat mesh face the camera, vertex
+//! extrusion requires outline normals lying in the plane of the mesh, such as
+//! those generated using [`GenerateOutlineNormalsFrom::ExternalBisector`].
+//! Jump flood has no such requirement.
+//!
+//! With the `sprite` feature enabled, outlines may be added to `Sprite`
+//! entities, including those using a texture atlas. The silhouette of a
+//! sprite is taken from the alpha channel of its image, unless an
+//! [`OutlineAlphaMask`] is specified, and is always outlined using jump
+//! flood.
+//!
+//! With the `ui` feature enabled, outlines may also be added to UI `Node`
+//! entities, using jump flood in the same way as sprites. The silhouette of
+//! an `ImageNode` is taken from the alpha channel of its image, provided it
+//! is stretched to fill the node, while other nodes are outlined as
+//! rectangles. Outlines of UI nodes are only visible to the node's target
+//! camera and are drawn before the UI, so they may be covered by any nodes
+//! overlapping them. Rounded corners given by a node's border radius are not
+//! reflected in its outline.
+//!
+//! The components styling an outline may also be gathered into an
+//! [`OutlineStyle`] asset and applied to entities using an
+//! [`OutlineStyleHandle`]. With the `style_loader` feature enabled, styles
+//! may be loaded from RON or JSON files and hot-reloaded.
+//!
+//! Changes to an outline may be animated using an [`OutlineTransition`],
+//! which eases the entity's outline towards a target over a duration.
+//!
+//! With the `animation` feature enabled, the width and colour of an
+//! [`OutlineVolume`], the offset of an [`OutlineStencil`], and the fields of
+//! an [`OutlinePlaneDepth`] may be keyframed in an `AnimationClip` using the
+//! properties [`OutlineVolumeWidth`], [`OutlineVolumeColour`],
+//! [`OutlineStencilOffset`], [`OutlinePlaneDepthOrigin`], and
+//! [`OutlinePlaneDepthOffset`]. Colours are animated in the colour space in
+//! which they are given.
+//!
+//! With the `picking` feature enabled, the [`OutlinePickingPlugin`] outlines
+//! entities while they are hovered or selected using Bevy's picking, styled
+//! by their [`HoverOutline`] and [`SelectOutline`] components.
+//!
+//! The shaders used to draw outline volumes with vertex extrusion may be
+//! replaced by implementing [`OutlineMaterial`] and adding the corresponding
+//! [`OutlineMaterialPlugin`]. Entities then use the material via an
+//! [`OutlineMaterialHandle`].
 
 use std::any::TypeId;
 
-use bevy::anti_alias::fxaa::fxaa;
-use bevy::anti_alias::smaa::smaa;
 use bevy::asset::{load_internal_asset, AssetEventSystems};
 use bevy::camera::visibility::{RenderLayers, VisibilitySystems};
-use bevy::core_pipeline::tonemapping::tonemapping;
-use bevy::core_pipeline::{Core3d, Core3dSystems};
+use bevy::core_pipeline::{Core2d, Core2dSystems, Core3d, Core3dSystems};
 use bevy::mesh::MeshVertexAttribute;
 use bevy::pbr::{MeshInputUniform, MeshUniform};
 use bevy::prelude::*;
@@ -55,13 +106,15 @@ use bevy::render::batching::no_gpu_preprocessing::{
 };
 use bevy::render::camera::DirtySpecializationSystems;
 use bevy::render::extract_component::{ExtractComponentPlugin, UniformComponentPlugin};
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_asset::prepare_assets;
 use bevy::render::render_phase::{
     sort_phase_system, AddRenderCommand, BinnedRenderPhasePlugin, DrawFunctions, PhaseItem,
     SortedRenderPhasePlugin,
 };
 use bevy::render::render_resource::{SpecializedMeshPipelines, VertexFormat};
 use bevy::render::renderer::RenderDevice;
-use bevy::render::texture::FallbackImage;
+use bevy::render::texture::{FallbackImage, GpuImage};
 use bevy::render::view::Msaa;
 use bevy::render::{
     init_gpu_resource, Render, RenderApp, RenderDebugFlags, RenderStartup, RenderSystems,
@@ -72,16 +125,18 @@ use crate::culling::{
     extract_outline_visible_entities, OutlineVisibleEntities, RenderExtractedOutlineEntities,
     RenderOutlineEntities,
 };
+use crate::material::OutlineMaterialBindGroups;
 use crate::msaa::{
-    msaa_extra_writeback_pass, prepare_msaa_extra_writeback_pipelines,
-    prepare_outline_view_textures, ResolvedOutlineMsaa,
+    prepare_msaa_extra_writeback_pipelines, prepare_outline_view_textures, ResolvedOutlineMsaa,
 };
-use crate::node::{outline_render_pass, OpaqueOutline, StencilOutline, TransparentOutline};
+use crate::node::{OccludedOutline, OpaqueOutline, StencilOutline, TransparentOutline};
+use crate::occlusion::{enable_scene_depth_binding, ResolvedSceneDepth};
 use crate::pipeline::{
-    init_outline_pipeline, OutlinePipeline, COMMON_SHADER_HANDLE, FRAGMENT_SHADER_HANDLE,
-    OUTLINE_SHADER_HANDLE,
+    init_outline_pipeline, OutlinePipeline, COMMON_SHADER_HANDLE, EXTRUDE_SHADER_HANDLE,
+    FRAGMENT_SHADER_HANDLE, OUTLINE_SHADER_HANDLE, SCENE_DEPTH_SHADER_HANDLE,
 };
 use crate::pipeline_key::compute_outline_key;
+use crate::placement::add_outline_passes;
 use crate::queue::{
     check_outline_entities_needing_specialisation, clear_dirty_outline_specialisations,
     expire_outline_specialisations_for_views, extract_outline_entities_needing_specialisation,
@@ -89,13 +144,25 @@ use crate::queue::{
     specialise_outlines, DirtyOutlineSpecialisations, OutlineCache,
     OutlineEntitiesNeedingSpecialisation, PendingOutlineQueues,
 };
-use crate::render::DrawOutline;
+use crate::render::{DrawOutline, DrawOutline2d, DrawOutlineVolume, DrawOutlineVolume2d};
+use crate::render_target::{prepare_outline_composite_pipelines, prepare_outline_target_views};
+#[cfg(feature = "sprite")]
+use crate::sprite::compute_sprite_outline;
+#[cfg(any(feature = "sprite", feature = "ui"))]
+use crate::sprite::SpriteOutlineQuad;
+use crate::style::apply_outline_style;
+#[cfg(feature = "style_loader")]
+use crate::style::{JsonOutlineStyleLoader, RonOutlineStyleLoader};
+use crate::transition::update_outline_transitions;
+#[cfg(feature = "ui")]
+use crate::ui::compute_ui_outline;
 use crate::uniforms::extract_outlines;
-use crate::uniforms::RenderOutlineInstances;
 use crate::uniforms::{
-    init_alpha_mask_bind_groups, prepare_alpha_mask_bind_groups,
+    init_alpha_mask_bind_groups, init_fill_texture_bind_groups, init_mesh_2d_bind_group,
+    prepare_alpha_mask_bind_groups, prepare_fill_texture_bind_groups,
     prepare_outline_instance_bind_group, OutlineInstanceUniform,
 };
+use crate::uniforms::{OutlineRingEntities, RenderOutlineInstances};
 use crate::view_uniforms::{
     extract_outline_view_uniforms, prepare_outline_view_bind_group, OutlineViewUniform,
 };
@@ -103,22 +170,47 @@ use crate::view_uniforms::{
 mod computed;
 mod culling;
 mod generate;
+mod material;
 mod msaa;
 mod node;
+mod occlusion;
 mod pipeline;
 mod pipeline_key;
+mod placement;
 mod propagate;
 mod queue;
 mod render;
+mod render_target;
+mod style;
+mod transition;
 mod uniforms;
 mod view_uniforms;
 
 pub use computed::*;
 pub use generate::*;
+pub use material::*;
+pub use style::*;
+pub use transition::*;
 
 #[cfg(feature = "flood")]
 mod flood;
 
+#[cfg(feature = "animation")]
+mod animation;
+#[cfg(feature = "animation")]
+pub use animation::*;
+
+#[cfg(feature = "picking")]
+mod picking;
+#[cfg(feature = "picking")]
+pub use picking::*;
+
+#[cfg(any(feature = "sprite", feature = "ui"))]
+mod sprite;
+
+#[cfg(feature = "ui")]
+mod ui;
+
 #[cfg(feature = "world_serialisation")]
 mod world_serialisation;
 #[cfg(feature = "world_serialisation")]
@@ -130,10 +222,21 @@ pub use world_serialisation::*;
 pub const ATTRIBUTE_OUTLINE_NORMAL: MeshVertexAttribute =
     MeshVertexAttribute::new("Outline_Normal", 1585570526, VertexFormat::Float32x3);
 
+/// A factor by which the width of the outline is scaled at the vertex when
+/// rendering with vertex extrusion.
+pub const ATTRIBUTE_OUTLINE_WIDTH: MeshVertexAttribute =
+    MeshVertexAttribute::new("Outline_Width", 1928075685, VertexFormat::Float32);
+
+/// A colour by which the colour of the outline is multiplied at the vertex
+/// when rendering with vertex extrusion.
+pub const ATTRIBUTE_OUTLINE_COLOUR: MeshVertexAttribute =
+    MeshVertexAttribute::new("Outline_Colour", 2064609411, VertexFormat::Float32x4);
+
 /// Specifies when a stencil should be rendered.
 #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Default))]
+#[cfg_attr(feature = "style_loader", derive(serde::Deserialize))]
 pub enum OutlineStencilEnabled {
     /// Always render a stencil
     #[default]
@@ -162,6 +265,8 @@ impl OutlineStencilEnabled {
 #[derive(Clone, Component, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
+#[cfg_attr(feature = "style_loader", derive(serde::Deserialize))]
+#[cfg_attr(feature = "style_loader", serde(default))]
 pub struct OutlineStencil {
     /// Controls when the stencil should be rendered
     pub enabled: OutlineStencilEnabled,
@@ -225,9 +330,11 @@ fn lerp_stencil(start: &OutlineStencil, end: &OutlineStencil, t: f32) -> Outline
 impl_lerp!(OutlineStencil, lerp_stencil);
 
 /// A component for rendering outlines around meshes.
-#[derive(Clone, Component, Default)]
+#[derive(Clone, Component)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
+#[cfg_attr(feature = "style_loader", derive(serde::Deserialize))]
+#[cfg_attr(feature = "style_loader", serde(default))]
 pub struct OutlineVolume {
     /// Enable rendering of the outline
     pub visible: bool,
@@ -235,6 +342,23 @@ pub struct OutlineVolume {
     pub width: f32,
     /// Colour of the outline
     pub colour: Color,
+    /// Multiplier applied to the colour of the outline, excluding alpha.
+    ///
+    /// Values greater than one push the outline into HDR intensities, so that
+    /// it drives bloom when drawn into an HDR target before post-processing.
+    /// See [`OutlinePassPlacement::PreTonemap`].
+    pub intensity: f32,
+}
+
+impl Default for OutlineVolume {
+    fn default() -> Self {
+        OutlineVolume {
+            visible: false,
+            width: 0.0,
+            colour: Color::default(),
+            intensity: 1.0,
+        }
+    }
 }
 
 fn lerp_bool(this: bool, other: bool, t: f32) -> bool {
@@ -252,11 +376,192 @@ fn lerp_volume(start: &OutlineVolume, end: &OutlineVolume, t: f32) -> OutlineVol
         visible: lerp_bool(start.visible, end.visible, t),
         width: start.width.lerp(end.width, t),
         colour: start.colour.mix(&end.colour, t),
+        intensity: start.intensity.lerp(end.intensity, t),
     }
 }
 
 impl_lerp!(OutlineVolume, lerp_volume);
 
+/// A component for drawing the occluded parts of an outline in a different
+/// style.
+///
+/// This only takes effect for cameras with [`OutlineSceneOcclusion`]. Parts
+/// of the outline volume lying behind the scene, which would otherwise be
+/// hidden, are instead drawn with this width and colour. This can be used to
+/// show selected entities through walls.
+#[derive(Clone, Component, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineOccludedStyle {
+    /// Enable rendering of the occluded outline
+    pub visible: bool,
+    /// Width of the occluded outline in logical pixels
+    pub width: f32,
+    /// Colour of the occluded outline
+    pub colour: Color,
+}
+
+fn lerp_occluded_style(
+    start: &OutlineOccludedStyle,
+    end: &OutlineOccludedStyle,
+    t: f32,
+) -> OutlineOccludedStyle {
+    OutlineOccludedStyle {
+        visible: lerp_bool(start.visible, end.visible, t),
+        width: start.width.lerp(end.width, t),
+        colour: start.colour.mix(&end.colour, t),
+    }
+}
+
+impl_lerp!(OutlineOccludedStyle, lerp_occluded_style);
+
+/// A component for drawing additional rings around an outline.
+///
+/// Each ring is drawn outside of the [`OutlineVolume`] and any rings before
+/// it in the list, so rings should be listed in order of increasing width.
+/// For example, a thin black ring followed by a wider yellow ring gives a
+/// two-tone border. Rings are only drawn while the [`OutlineVolume`] is
+/// visible, and jump flood outlines reuse the same distance field for every
+/// ring.
+#[derive(Clone, Component, Default, Deref, DerefMut)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineRings(pub Vec<OutlineVolume>);
+
+/// The shape of the falloff of an [`OutlineGlow`].
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Default))]
+pub enum OutlineGlowCurve {
+    /// Alpha decreases linearly with distance
+    Linear,
+    /// Alpha eases in and out using a smoothstep (default)
+    #[default]
+    Smooth,
+    /// Alpha decreases quadratically, fading quickly near the inner edge
+    Quadratic,
+}
+
+/// A component for giving jump flood outlines a soft glow.
+///
+/// When present with a non-zero `falloff_width`, the outline volume is drawn
+/// at full opacity out to `inner_width` and then fades to transparent over
+/// the following `falloff_width`, in place of the volume's hard edge. The
+/// width of the [`OutlineVolume`] is ignored while the glow is in effect.
+/// Only the [`OutlineMode::FloodFlat`] and [`OutlineMode::FloodReal`] modes
+/// support glow.
+#[derive(Clone, Component, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineGlow {
+    /// Width of the fully opaque part of the glow in logical pixels
+    pub inner_width: f32,
+    /// Width over which the glow fades out in logical pixels
+    pub falloff_width: f32,
+    /// Shape of the fade
+    pub curve: OutlineGlowCurve,
+}
+
+fn lerp_glow(start: &OutlineGlow, end: &OutlineGlow, t: f32) -> OutlineGlow {
+    OutlineGlow {
+        inner_width: start.inner_width.lerp(end.inner_width, t),
+        falloff_width: start.falloff_width.lerp(end.falloff_width, t),
+        curve: if t < 1.0 { start.curve } else { end.curve },
+    }
+}
+
+impl_lerp!(OutlineGlow, lerp_glow);
+
+/// A component for drawing jump flood outlines as dashes.
+///
+/// The dash pattern runs around the silhouette and moves along it at `speed`
+/// to give a marching-ants effect. The position of each pixel along the
+/// pattern is estimated from the direction to the nearest point on the
+/// silhouette, as seen from the centre of the outline, so the pattern is most
+/// regular for convex shapes and has a seam on the left-hand side. Only the
+/// [`OutlineMode::FloodFlat`] and [`OutlineMode::FloodReal`] modes support
+/// dashes, and rings and the occluded style are not affected.
+///
+/// A `dash_length` of zero draws a solid outline.
+#[derive(Clone, Component, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineDashes {
+    /// Length of each dash in logical pixels
+    pub dash_length: f32,
+    /// Length of the gap between dashes in logical pixels
+    pub gap_length: f32,
+    /// Speed at which the dashes move around the outline in logical pixels
+    /// per second
+    pub speed: f32,
+}
+
+fn lerp_dashes(start: &OutlineDashes, end: &OutlineDashes, t: f32) -> OutlineDashes {
+    OutlineDashes {
+        dash_length: start.dash_length.lerp(end.dash_length, t),
+        gap_length: start.gap_length.lerp(end.gap_length, t),
+        speed: start.speed.lerp(end.speed, t),
+    }
+}
+
+impl_lerp!(OutlineDashes, lerp_dashes);
+
+/// A component for merging the jump flood outlines of several entities into a
+/// single silhouette.
+///
+/// Entities with the same group number are outlined together regardless of
+/// their depth, for example a character and the weapon they are holding.
+/// The merged outline takes its style and plane depth from the member of the
+/// group nearest to the camera. Only the [`OutlineMode::FloodFlat`] and
+/// [`OutlineMode::FloodReal`] modes are affected, as extruded outlines of the
+/// same colour already merge where they overlap.
+#[derive(Copy, Clone, Component, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineGroup(pub u32);
+
+/// A colour stop in an [`OutlineGradient`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct OutlineGradientStop {
+    /// Position of the stop from 0.0 at the silhouette to 1.0 at the outer edge
+    pub position: f32,
+    /// Colour of the outline at this stop
+    pub colour: Color,
+}
+
+/// A component for colouring jump flood outlines with a gradient.
+///
+/// The colour of each pixel of the outline volume is taken from a colour ramp
+/// by its normalised distance from the silhouette, in place of the colour of
+/// the [`OutlineVolume`]. This can be used to fade from a rim colour to an
+/// edge colour. Only the [`OutlineMode::FloodFlat`] and
+/// [`OutlineMode::FloodReal`] modes support gradients, and rings and the
+/// occluded style are not affected.
+#[derive(Clone, Component)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub enum OutlineGradient {
+    /// Interpolate between colour stops in order of increasing position.
+    ///
+    /// Stops beyond [`OutlineGradient::MAX_STOPS`] are ignored and an empty
+    /// list disables the gradient.
+    Stops(Vec<OutlineGradientStop>),
+    /// Interpolate along the first row of a texture from left to right.
+    Texture(Handle<Image>),
+}
+
+impl OutlineGradient {
+    /// The maximum number of colour stops in a gradient.
+    pub const MAX_STOPS: usize = 8;
+}
+
+impl Default for OutlineGradient {
+    fn default() -> Self {
+        OutlineGradient::Stops(Vec::new())
+    }
+}
+
 /// A component for specifying what layer(s) the outline should be rendered for.
 #[derive(Component, Clone, PartialEq, Eq, PartialOrd, Ord, Deref, DerefMut, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
@@ -273,6 +578,7 @@ impl From<RenderLayers> for OutlineRenderLayers {
 #[derive(Clone, Component, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
+#[cfg_attr(feature = "style_loader", derive(serde::Deserialize))]
 #[non_exhaustive]
 pub enum OutlineMode {
     /// Vertex extrusion flattened into a billboard. (default)
@@ -283,6 +589,9 @@ pub enum OutlineMode {
     /// Jump-flood into a billboard.
     #[cfg(feature = "flood")]
     FloodFlat,
+    /// Jump-flood with each pixel taking the depth of its nearest seed.
+    #[cfg(feature = "flood")]
+    FloodReal,
 }
 
 /// A resource which controls the default [`OutlineMode`] used when an entity
@@ -297,6 +606,7 @@ pub struct GlobalOutlineMode(pub OutlineMode);
 #[derive(Clone, Component, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
+#[cfg_attr(feature = "style_loader", derive(serde::Deserialize))]
 #[non_exhaustive]
 pub enum OutlineFace {
     /// Render only the front face of the outline. (default)
@@ -306,6 +616,99 @@ pub enum OutlineFace {
     DoubleSided,
 }
 
+/// A component which controls the units of the widths and offsets of an outline.
+///
+/// This applies to the widths of the [`OutlineVolume`], [`OutlineStencil`],
+/// and any other outline styles on the entity.
+#[derive(Copy, Clone, Component, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub enum OutlineWidthUnit {
+    /// Logical pixels, which are scaled by the window's scale factor. (default)
+    #[default]
+    LogicalPixels,
+    /// Physical pixels of the render target.
+    PhysicalPixels,
+    /// World units, so that the outline shrinks with distance like real
+    /// geometry.
+    WorldUnits,
+}
+
+impl OutlineWidthUnit {
+    /// Converts a width in this unit to logical pixels, given the view's
+    /// scale factors and the clip-space w coordinate of the outline.
+    pub(crate) fn to_logical(
+        self,
+        width: f32,
+        scale_physical_from_logical: f32,
+        scale_logical_from_world: f32,
+        clip_w: f32,
+    ) -> f32 {
+        match self {
+            OutlineWidthUnit::LogicalPixels => width,
+            OutlineWidthUnit::PhysicalPixels => width / scale_physical_from_logical,
+            OutlineWidthUnit::WorldUnits => {
+                width * scale_logical_from_world / clip_w.max(f32::EPSILON)
+            }
+        }
+    }
+}
+
+/// A component for scaling the width of an outline by its distance from the
+/// camera.
+///
+/// The distance is measured from each camera to the outline plane, so an
+/// entity seen by several cameras gets an appropriate width in each. Between
+/// `near` and `far` the width is interpolated from its full value down to
+/// `min_width`, and it is always clamped between `min_width` and `max_width`.
+/// Beyond `far`, the outline fades out over `fade_out`. Widths here are in
+/// logical pixels regardless of the [`OutlineWidthUnit`]. Stencils are not
+/// scaled.
+///
+/// The default is no scaling.
+#[derive(Clone, Component, PartialEq, Debug)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineDistanceScaling {
+    /// Distance up to which the outline has its full width
+    pub near: f32,
+    /// Distance at which the outline has been reduced to its minimum width
+    pub far: f32,
+    /// Minimum width of the outline in logical pixels
+    pub min_width: f32,
+    /// Maximum width of the outline in logical pixels
+    pub max_width: f32,
+    /// Distance beyond `far` over which the outline fades to transparent
+    pub fade_out: f32,
+}
+
+impl Default for OutlineDistanceScaling {
+    fn default() -> Self {
+        OutlineDistanceScaling {
+            near: 0.0,
+            far: f32::MAX,
+            min_width: 0.0,
+            max_width: f32::MAX,
+            fade_out: f32::MAX,
+        }
+    }
+}
+
+impl OutlineDistanceScaling {
+    /// Returns the width in logical pixels of an outline at the given distance.
+    pub(crate) fn scale_width(&self, width: f32, distance: f32) -> f32 {
+        let t = ((distance - self.near) / (self.far - self.near).max(f32::EPSILON)).clamp(0.0, 1.0);
+        width
+            .lerp(self.min_width, t)
+            .clamp(self.min_width, self.max_width.max(self.min_width))
+    }
+
+    /// Returns whether the outline fades out at any distance.
+    pub(crate) fn fades(&self) -> bool {
+        self.fade_out < f32::MAX
+    }
+}
+
 /// A component which controls the depth sorting of flat outlines and stencils.
 ///
 /// By flattening an outline into a plane, we avoid it being partially clipped
@@ -327,6 +730,8 @@ pub enum OutlineFace {
 #[derive(Clone, Component, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
+#[cfg_attr(feature = "style_loader", derive(serde::Deserialize))]
+#[cfg_attr(feature = "style_loader", serde(default))]
 pub struct OutlinePlaneDepth {
     /// The point in model-space through which the outline plane passes, before
     /// the view dependent offset is applied.
@@ -357,6 +762,7 @@ pub struct StopPropagateOutline;
 #[derive(Copy, Clone, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Default))]
+#[cfg_attr(feature = "style_loader", derive(serde::Deserialize))]
 pub enum TextureChannel {
     R,
     G,
@@ -392,6 +798,50 @@ pub struct OutlineAlphaMask {
     pub threshold: f32,
 }
 
+/// How an [`OutlineFillTexture`] is mapped onto the outline.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Default))]
+pub enum OutlineTextureMapping {
+    /// Tile the texture in screen space. (default)
+    #[default]
+    Screen,
+    /// Tile the texture along the outline, with the horizontal axis running
+    /// around the silhouette and the vertical axis running outwards from it.
+    ///
+    /// This is only supported by jump-flood modes. Extrusion modes fall back
+    /// to screen space.
+    AlongOutline,
+}
+
+/// A component for modulating the colour of the outline volume with a
+/// texture.
+///
+/// The outline colour is multiplied by the colour sampled from the texture,
+/// which can be used for effects such as hatching or noisy ink. The texture
+/// is tiled, so its sampler should use a repeating address mode.
+#[derive(Clone, Component)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineFillTexture {
+    /// The texture to fill the outline with.
+    pub texture: Option<Handle<Image>>,
+    /// How the texture is mapped onto the outline.
+    pub mapping: OutlineTextureMapping,
+    /// Size of one tile of the texture in logical pixels.
+    pub tile_size: Vec2,
+}
+
+impl Default for OutlineFillTexture {
+    fn default() -> Self {
+        OutlineFillTexture {
+            texture: None,
+            mapping: OutlineTextureMapping::Screen,
+            tile_size: Vec2::splat(64.0),
+        }
+    }
+}
+
 /// A component for warming up different specialisations of the outline pipeline.
 ///
 /// When animating a property which causes the required pipeline specialisation
@@ -440,6 +890,76 @@ pub enum OutlineMsaa {
     Msaa(Msaa),
 }
 
+/// A view-level component which causes outlines to be occluded by the scene.
+///
+/// By default, outlines are only occluded by the stencils of other outlined
+/// entities. Adding this component to a camera additionally tests outline
+/// volumes against the depth buffer of the main 3D pass, so that walls,
+/// terrain, and other geometry without an [`OutlineStencil`] can hide the
+/// outlines behind them. The hidden parts of an outline can be drawn in a
+/// different style using [`OutlineOccludedStyle`].
+///
+/// The camera's depth texture must be sampled to do this, so
+/// `TEXTURE_BINDING` is added to its [`Camera3d::depth_texture_usages`]
+/// automatically.
+#[derive(Component, Clone, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineSceneOcclusion;
+
+/// A view-level component which renders outlines into an image instead of
+/// the camera's view target.
+///
+/// The image must be the same size as the camera's physical target and have
+/// the `RENDER_ATTACHMENT` usage. It is cleared to transparent at the start of
+/// each frame, so its alpha channel gives the coverage of the outlines and
+/// may be used as a mask by custom post-processing. Colours are premultiplied
+/// by alpha.
+///
+/// If `composite` is true, the outlines are additionally blended onto the view
+/// target after being drawn.
+#[derive(Component, Clone, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineRenderTarget {
+    /// The image to render outlines into.
+    pub image: Handle<Image>,
+    /// Whether to also composite the outlines onto the view target.
+    pub composite: bool,
+}
+
+/// A view-level component which selects where outlines are drawn in relation
+/// to the camera's post-processing.
+///
+/// Outlines are drawn after tone-mapping by default.
+#[derive(Component, Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub enum OutlinePassPlacement {
+    /// Draw outlines after the main pass and before any post-processing.
+    /// Outlines on HDR cameras then participate in bloom and exposure, and
+    /// their colours are tone-mapped along with the rest of the scene.
+    PreTonemap,
+    /// Draw outlines after tone-mapping and before anti-aliasing.
+    #[default]
+    PostTonemap,
+    /// Draw outlines after anti-aliasing, so that they are not smoothed by
+    /// FXAA or SMAA.
+    PostAntiAlias,
+}
+
+/// A resource which substitutes the functions used to extrude the vertices of
+/// outlines.
+///
+/// The shader must declare `#define_import_path bevy_mod_outline::custom_extrude`
+/// and define the functions `outline_extrude_direction` and
+/// `outline_extrude_offset`, with the same signatures as their built-in
+/// counterparts in `bevy_mod_outline::extrude`. The built-in functions may be
+/// imported and called from the replacements. Skinning, morph targets, and
+/// flat depth are handled by the outline shader as usual.
+#[derive(Resource, Clone, ExtractResource)]
+pub struct OutlineExtrudeShader(pub Handle<Shader>);
+
 // This makes `SetMeshBindGroup` work with CPU drawn outlines when GPU pre-processing is enabled
 pub(crate) fn add_dummy_phase_buffer<P: PhaseItem + 'static>(
     bibs: &mut gpu_preprocessing::BatchedInstanceBuffers<MeshUniform, MeshInputUniform>,
@@ -460,6 +980,7 @@ fn add_dummy_phase_buffers(
     add_dummy_phase_buffer::<StencilOutline>(&mut bibs);
     add_dummy_phase_buffer::<OpaqueOutline>(&mut bibs);
     add_dummy_phase_buffer::<TransparentOutline>(&mut bibs);
+    add_dummy_phase_buffer::<OccludedOutline>(&mut bibs);
 }
 
 /// Adds support for rendering outlines.
@@ -497,9 +1018,25 @@ impl Plugin for OutlinePlugin {
             "fragment.wgsl",
             Shader::from_wgsl
         );
+        load_internal_asset!(
+            app,
+            SCENE_DEPTH_SHADER_HANDLE,
+            "scene_depth.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            EXTRUDE_SHADER_HANDLE,
+            "extrude.wgsl",
+            Shader::from_wgsl
+        );
 
         app.add_plugins((
             ExtractComponentPlugin::<ResolvedOutlineMsaa>::default(),
+            ExtractComponentPlugin::<ResolvedSceneDepth>::default(),
+            ExtractComponentPlugin::<OutlineRenderTarget>::default(),
+            ExtractComponentPlugin::<OutlinePassPlacement>::default(),
+            ExtractResourcePlugin::<OutlineExtrudeShader>::default(),
             UniformComponentPlugin::<OutlineViewUniform>::default(),
             BinnedRenderPhasePlugin::<StencilOutline, OutlinePipeline>::new(
                 RenderDebugFlags::empty(),
@@ -510,11 +1047,17 @@ impl Plugin for OutlinePlugin {
             SortedRenderPhasePlugin::<TransparentOutline, OutlinePipeline>::new(
                 RenderDebugFlags::empty(),
             ),
+            SortedRenderPhasePlugin::<OccludedOutline, OutlinePipeline>::new(
+                RenderDebugFlags::empty(),
+            ),
         ))
         .register_required_components::<OutlineStencil, ComputedOutline>()
         .register_required_components::<OutlineVolume, ComputedOutline>()
         .register_required_components::<InheritOutline, ComputedOutline>()
         .register_required_components::<PropagateOutline, ComputedOutline>()
+        .register_required_components::<OutlineStyleHandle, ComputedOutline>()
+        .register_required_components::<OutlineTransition, ComputedOutline>()
+        .init_asset::<OutlineStyle>()
         .insert_resource(GlobalOutlineMode(self.mode.clone()))
         .init_resource::<OutlineEntitiesNeedingSpecialisation>()
         .init_resource::<OutlineVisibleEntities>()
@@ -522,6 +1065,10 @@ impl Plugin for OutlinePlugin {
             PostUpdate,
             (
                 clean_up_computed_outline,
+                apply_outline_style
+                    .after(AssetEventSystems)
+                    .before(compute_outline),
+                update_outline_transitions.before(compute_outline),
                 compute_outline
                     .after(TransformSystems::Propagate)
                     .after(VisibilitySystems::VisibilityPropagate),
@@ -533,16 +1080,24 @@ impl Plugin for OutlinePlugin {
                 check_outline_view_visibility
                     .after(VisibilitySystems::CheckVisibility)
                     .after(compute_outline),
+                enable_scene_depth_binding,
             ),
         )
         .sub_app_mut(RenderApp)
         .init_resource::<DrawFunctions<StencilOutline>>()
         .init_resource::<DrawFunctions<OpaqueOutline>>()
         .init_resource::<DrawFunctions<TransparentOutline>>()
+        .init_resource::<DrawFunctions<OccludedOutline>>()
         .init_resource::<SpecializedMeshPipelines<OutlinePipeline>>()
+        .init_resource::<OutlineMaterialBindGroups>()
         .add_render_command::<StencilOutline, DrawOutline>()
-        .add_render_command::<OpaqueOutline, DrawOutline>()
-        .add_render_command::<TransparentOutline, DrawOutline>()
+        .add_render_command::<OpaqueOutline, DrawOutlineVolume>()
+        .add_render_command::<TransparentOutline, DrawOutlineVolume>()
+        .add_render_command::<OccludedOutline, DrawOutlineVolume>()
+        .add_render_command::<StencilOutline, DrawOutline2d>()
+        .add_render_command::<OpaqueOutline, DrawOutlineVolume2d>()
+        .add_render_command::<TransparentOutline, DrawOutlineVolume2d>()
+        .add_render_command::<OccludedOutline, DrawOutlineVolume2d>()
         .add_systems(
             ExtractSchedule,
             (
@@ -562,6 +1117,7 @@ impl Plugin for OutlinePlugin {
             (
                 prepare_outline_view_textures,
                 prepare_msaa_extra_writeback_pipelines,
+                prepare_outline_composite_pipelines,
             )
                 .in_set(RenderSystems::Prepare),
         )
@@ -571,6 +1127,7 @@ impl Plugin for OutlinePlugin {
                 prepare_outline_view_bind_group,
                 prepare_outline_instance_bind_group,
                 prepare_alpha_mask_bind_groups,
+                prepare_fill_texture_bind_groups,
             )
                 .in_set(RenderSystems::PrepareBindGroups),
         )
@@ -578,6 +1135,12 @@ impl Plugin for OutlinePlugin {
             Render,
             collect_outline_cpu_culled_entities.in_set(RenderSystems::PrepareAssets),
         )
+        .add_systems(
+            Render,
+            prepare_outline_target_views
+                .in_set(RenderSystems::PrepareAssets)
+                .after(prepare_assets::<GpuImage>),
+        )
         .add_systems(
             Render,
             specialise_outlines.in_set(RenderSystems::PrepareMeshes),
@@ -588,23 +1151,31 @@ impl Plugin for OutlinePlugin {
         )
         .add_systems(
             Render,
-            sort_phase_system::<TransparentOutline>.in_set(RenderSystems::PhaseSort),
+            (
+                sort_phase_system::<TransparentOutline>,
+                sort_phase_system::<OccludedOutline>,
+            )
+                .in_set(RenderSystems::PhaseSort),
         )
         .add_systems(
             Render,
             clear_batched_cpu_instance_buffers::<OutlinePipeline>
                 .in_set(RenderSystems::Cleanup)
                 .after(RenderSystems::Render),
-        )
-        // Outlining occurs after tone-mapping.
-        .add_systems(
+        );
+
+        let render_app = app.sub_app_mut(RenderApp);
+        add_outline_passes(
+            render_app,
             Core3d,
-            (msaa_extra_writeback_pass, outline_render_pass)
-                .chain()
-                .in_set(Core3dSystems::PostProcess)
-                .after(tonemapping)
-                .before(fxaa)
-                .before(smaa),
+            Core3dSystems::MainPass,
+            Core3dSystems::PostProcess,
+        );
+        add_outline_passes(
+            render_app,
+            Core2d,
+            Core2dSystems::MainPass,
+            Core2dSystems::PostProcess,
         );
 
         propagate::add_propagate_observers(app);
@@ -616,8 +1187,22 @@ impl Plugin for OutlinePlugin {
             .register_type::<OutlineMode>()
             .register_type::<GlobalOutlineMode>()
             .register_type::<OutlineFace>()
+            .register_type::<OutlineWidthUnit>()
+            .register_type::<OutlineDistanceScaling>()
             .register_type::<OutlineAlphaMask>()
+            .register_type::<OutlineFillTexture>()
             .register_type::<OutlineMsaa>()
+            .register_type::<OutlineSceneOcclusion>()
+            .register_type::<OutlineRenderTarget>()
+            .register_type::<OutlinePassPlacement>()
+            .register_type::<OutlineOccludedStyle>()
+            .register_type::<OutlineRings>()
+            .register_type::<OutlineGlow>()
+            .register_type::<OutlineGradient>()
+            .register_type::<OutlineDashes>()
+            .register_type::<OutlineGroup>()
+            .register_type::<OutlineStyleHandle>()
+            .register_type::<OutlineTransition>()
             .register_type::<InheritOutline>()
             .register_type::<PropagateOutline>()
             .register_type::<StopPropagateOutline>();
@@ -625,14 +1210,43 @@ impl Plugin for OutlinePlugin {
         #[cfg(feature = "world_serialisation")]
         app.init_resource::<AsyncWorldInheritOutlineSystems>();
 
+        #[cfg(feature = "style_loader")]
+        app.register_asset_loader(RonOutlineStyleLoader)
+            .register_asset_loader(JsonOutlineStyleLoader);
+
         #[cfg(feature = "flood")]
         app.add_plugins(flood::FloodPlugin);
+
+        #[cfg(any(feature = "sprite", feature = "ui"))]
+        app.init_resource::<SpriteOutlineQuad>();
+
+        #[cfg(feature = "sprite")]
+        app.add_systems(
+            PostUpdate,
+            compute_sprite_outline
+                .after(compute_outline)
+                .after(AssetEventSystems)
+                .before(compute_outline_key),
+        );
+
+        #[cfg(feature = "ui")]
+        app.add_systems(
+            PostUpdate,
+            compute_ui_outline
+                .after(compute_outline)
+                .after(bevy::ui::UiSystems::Layout)
+                .after(bevy::camera::CameraUpdateSystems)
+                .after(AssetEventSystems)
+                .before(compute_outline_key)
+                .before(check_outline_view_visibility),
+        );
     }
 
     fn finish(&self, app: &mut App) {
         let render_app = app.sub_app_mut(RenderApp);
         render_app
             .init_resource::<RenderOutlineInstances>()
+            .init_resource::<OutlineRingEntities>()
             .init_resource::<RenderExtractedOutlineEntities>()
             .init_resource::<RenderOutlineEntities>()
             .init_resource::<PendingOutlineQueues>()
@@ -643,9 +1257,10 @@ impl Plugin for OutlinePlugin {
                 (
                     (init_outline_pipeline, init_outline_instance_buffer)
                         .after(bevy::pbr::MeshPipelineSystems),
-                    init_alpha_mask_bind_groups
+                    (init_alpha_mask_bind_groups, init_fill_texture_bind_groups)
                         .after(init_outline_pipeline)
                         .after(init_gpu_resource::<FallbackImage>),
+                    init_mesh_2d_bind_group.after(init_outline_pipeline),
                 ),
             )
             .add_systems(
@@ -669,3 +1284,68 @@ fn init_outline_instance_buffer(mut commands: Commands, render_device: Res<Rende
         &render_device.limits(),
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width_unit_to_logical() {
+        assert_eq!(
+            OutlineWidthUnit::LogicalPixels.to_logical(4.0, 2.0, 100.0, 10.0),
+            4.0
+        );
+        assert_eq!(
+            OutlineWidthUnit::PhysicalPixels.to_logical(4.0, 2.0, 100.0, 10.0),
+            2.0
+        );
+        assert_eq!(
+            OutlineWidthUnit::WorldUnits.to_logical(0.5, 2.0, 100.0, 10.0),
+            5.0
+        );
+
+        // World units remain finite at the plane of the camera
+        assert!(OutlineWidthUnit::WorldUnits
+            .to_logical(0.5, 2.0, 100.0, 0.0)
+            .is_finite());
+    }
+
+    #[test]
+    fn test_distance_scaling_scale_width() {
+        // The default scaling leaves widths unchanged
+        let scaling = OutlineDistanceScaling::default();
+        assert_eq!(scaling.scale_width(2.0, 100.0), 2.0);
+
+        let scaling = OutlineDistanceScaling {
+            near: 10.0,
+            far: 20.0,
+            min_width: 1.0,
+            max_width: 3.0,
+            ..default()
+        };
+        assert_eq!(scaling.scale_width(2.0, 5.0), 2.0);
+        assert_eq!(scaling.scale_width(2.0, 15.0), 1.5);
+        assert_eq!(scaling.scale_width(2.0, 30.0), 1.0);
+        assert_eq!(scaling.scale_width(5.0, 0.0), 3.0);
+        assert_eq!(scaling.scale_width(0.5, 0.0), 1.0);
+
+        // Coincident near and far distances step between the widths
+        let scaling = OutlineDistanceScaling {
+            near: 10.0,
+            far: 10.0,
+            ..default()
+        };
+        assert_eq!(scaling.scale_width(2.0, 5.0), 2.0);
+        assert_eq!(scaling.scale_width(2.0, 15.0), 0.0);
+    }
+
+    #[test]
+    fn test_distance_scaling_fades() {
+        assert!(!OutlineDistanceScaling::default().fades());
+        assert!(OutlineDistanceScaling {
+            fade_out: 5.0,
+            ..default()
+        }
+        .fades());
+    }
+}
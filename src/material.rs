@@ -0,0 +1,345 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+use bevy::ecs::system::{lifetimeless::SRes, SystemParamItem};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::{
+    render_asset::{PrepareAssetError, RenderAsset, RenderAssetPlugin, RenderAssets},
+    render_resource::{
+        AsBindGroup, AsBindGroupError, BindGroup, BindGroupLayoutDescriptor, PipelineCache,
+    },
+    renderer::RenderDevice,
+    Extract, Render, RenderApp, RenderStartup, RenderSystems,
+};
+use bevy::shader::ShaderRef;
+
+use crate::pipeline::{init_outline_pipeline, OutlinePipeline};
+use crate::pipeline_key::{compute_outline_key, ComputedOutlineKey};
+use crate::queue::check_outline_entities_needing_specialisation;
+use crate::uniforms::{extract_outlines, RenderOutlineInstances};
+
+/// Materials are identified in the pipeline key by an index of this many bits.
+const MATERIAL_INDEX_BITS: u32 = 8;
+
+/// A trait for materials which replace the shaders used to draw outline
+/// volumes.
+///
+/// The bindings of the material are placed in `@group(3)`. A custom fragment
+/// shader receives the `VertexOutput` of the built-in vertex shader, which can
+/// be imported from `bevy_mod_outline::common`. It is responsible for writing
+/// `frag_depth` when `FLAT_DEPTH` is defined, as the built-in shader does.
+///
+/// The built-in fragment shader also discards fragments according to the
+/// scene depth when [`OutlineSceneOcclusion`](crate::OutlineSceneOcclusion)
+/// is enabled. A custom fragment shader should do likewise by calling
+/// `outline_scene_depth_test`, imported from `bevy_mod_outline::scene_depth`,
+/// and discarding the fragment if it returns false. The same shader draws the
+/// occluded style of [`OutlineOccludedStyle`](crate::OutlineOccludedStyle),
+/// in which case `OCCLUDED` is defined and `volume_colour` holds the colour of
+/// the occluded style.
+///
+/// Materials only apply to outlines drawn using vertex extrusion and replace
+/// any [`OutlineFillTexture`](crate::OutlineFillTexture).
+pub trait OutlineMaterial: Asset + AsBindGroup + Clone + Sized {
+    /// Returns this material's vertex shader. If [`ShaderRef::Default`] is
+    /// returned, the built-in outline vertex shader is used.
+    fn vertex_shader() -> ShaderRef {
+        ShaderRef::Default
+    }
+
+    /// Returns this material's fragment shader. If [`ShaderRef::Default`] is
+    /// returned, the built-in outline fragment shader is used.
+    fn fragment_shader() -> ShaderRef {
+        ShaderRef::Default
+    }
+}
+
+/// A component for drawing the outline volume of an entity using an
+/// [`OutlineMaterial`].
+///
+/// The material's type must be registered by adding an
+/// [`OutlineMaterialPlugin`].
+#[derive(Component, Clone, Debug, Deref, DerefMut, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct OutlineMaterialHandle<M: OutlineMaterial>(pub Handle<M>);
+
+/// Adds support for drawing outline volumes using the [`OutlineMaterial`] `M`.
+pub struct OutlineMaterialPlugin<M: OutlineMaterial>(PhantomData<M>);
+
+impl<M: OutlineMaterial> Default for OutlineMaterialPlugin<M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<M: OutlineMaterial> Plugin for OutlineMaterialPlugin<M> {
+    fn build(&self, app: &mut App) {
+        let index = app
+            .world_mut()
+            .get_resource_or_init::<OutlineMaterialIndices>()
+            .allocate();
+
+        #[cfg(feature = "reflect")]
+        app.register_type::<OutlineMaterialHandle<M>>();
+
+        app.init_asset::<M>()
+            .insert_resource(OutlineMaterialType::<M>::new(index))
+            .add_plugins(RenderAssetPlugin::<PreparedOutlineMaterial<M>>::default())
+            .add_systems(
+                PostUpdate,
+                compute_outline_material_key::<M>
+                    .after(compute_outline_key)
+                    .before(check_outline_entities_needing_specialisation),
+            )
+            .sub_app_mut(RenderApp)
+            .insert_resource(OutlineMaterialType::<M>::new(index))
+            .add_systems(
+                RenderStartup,
+                init_outline_material::<M>.after(init_outline_pipeline),
+            )
+            .add_systems(
+                ExtractSchedule,
+                extract_outline_materials::<M>.after(extract_outlines),
+            )
+            .add_systems(
+                Render,
+                prepare_outline_material_bind_groups::<M>.in_set(RenderSystems::PrepareBindGroups),
+            );
+    }
+}
+
+/// The number of material types registered, used to allocate their indices.
+#[derive(Resource, Default)]
+struct OutlineMaterialIndices(u32);
+
+impl OutlineMaterialIndices {
+    /// Allocates the index of a new material type. Index zero is reserved
+    /// for outlines without a material.
+    fn allocate(&mut self) -> u32 {
+        self.0 += 1;
+        assert!(
+            self.0 < 1 << MATERIAL_INDEX_BITS,
+            "Too many outline material types"
+        );
+        self.0
+    }
+}
+
+/// The index which identifies the material type `M` in the pipeline key.
+#[derive(Resource)]
+pub(crate) struct OutlineMaterialType<M: OutlineMaterial> {
+    index: u32,
+    marker: PhantomData<M>,
+}
+
+impl<M: OutlineMaterial> OutlineMaterialType<M> {
+    fn new(index: u32) -> Self {
+        OutlineMaterialType {
+            index,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// The bind group layout and shaders of a material type, by which the outline
+/// pipeline is specialised for volumes drawn with that material.
+#[derive(Clone)]
+pub(crate) struct OutlineMaterialSpecialisation {
+    pub(crate) layout: BindGroupLayoutDescriptor,
+    pub(crate) vertex_shader: Option<Handle<Shader>>,
+    pub(crate) fragment_shader: Option<Handle<Shader>>,
+}
+
+/// The bind groups of all prepared materials, by asset.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct OutlineMaterialBindGroups(HashMap<UntypedAssetId, BindGroup>);
+
+pub(crate) struct PreparedOutlineMaterial<M: OutlineMaterial> {
+    bind_group: BindGroup,
+    marker: PhantomData<M>,
+}
+
+impl<M: OutlineMaterial> RenderAsset for PreparedOutlineMaterial<M> {
+    type SourceAsset = M;
+    type Param = (
+        SRes<RenderDevice>,
+        SRes<PipelineCache>,
+        SRes<OutlinePipeline>,
+        SRes<OutlineMaterialType<M>>,
+        M::Param,
+    );
+
+    fn prepare_asset(
+        material: Self::SourceAsset,
+        _asset_id: AssetId<Self::SourceAsset>,
+        (render_device, pipeline_cache, pipeline, material_type, param): &mut SystemParamItem<
+            Self::Param,
+        >,
+        _previous_asset: Option<&Self>,
+    ) -> Result<Self, PrepareAssetError<Self::SourceAsset>> {
+        let layout = &pipeline.materials[&material_type.index].layout;
+        match material.as_bind_group(layout, render_device, pipeline_cache, param) {
+            Ok(prepared) => Ok(PreparedOutlineMaterial {
+                bind_group: prepared.bind_group,
+                marker: PhantomData,
+            }),
+            Err(AsBindGroupError::RetryNextUpdate) => {
+                Err(PrepareAssetError::RetryNextUpdate(material))
+            }
+            Err(other) => Err(PrepareAssetError::AsBindGroupError(other)),
+        }
+    }
+}
+
+fn load_shader(asset_server: &AssetServer, shader: ShaderRef) -> Option<Handle<Shader>> {
+    match shader {
+        ShaderRef::Default => None,
+        ShaderRef::Handle(handle) => Some(handle),
+        ShaderRef::Path(path) => Some(asset_server.load(path)),
+    }
+}
+
+fn init_outline_material<M: OutlineMaterial>(
+    mut outline_pipeline: ResMut<OutlinePipeline>,
+    material_type: Res<OutlineMaterialType<M>>,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+) {
+    outline_pipeline.materials.insert(
+        material_type.index,
+        OutlineMaterialSpecialisation {
+            layout: M::bind_group_layout_descriptor(&render_device),
+            vertex_shader: load_shader(&asset_server, M::vertex_shader()),
+            fragment_shader: load_shader(&asset_server, M::fragment_shader()),
+        },
+    );
+}
+
+/// Sets the material index in the pipeline keys of entities with a material,
+/// and clears it when their material is removed.
+#[allow(clippy::type_complexity)]
+fn compute_outline_material_key<M: OutlineMaterial>(
+    mut with_material: Query<(Ref<OutlineMaterialHandle<M>>, &mut ComputedOutlineKey)>,
+    mut without_material: Query<&mut ComputedOutlineKey, Without<OutlineMaterialHandle<M>>>,
+    mut removed: RemovedComponents<OutlineMaterialHandle<M>>,
+    material_type: Res<OutlineMaterialType<M>>,
+) {
+    for entity in removed.read() {
+        if let Ok(mut key) = without_material.get_mut(entity) {
+            if key.0.material_index() == material_type.index {
+                key.0 = key.0.with_material_index(0);
+            }
+        }
+    }
+    for (material, mut key) in with_material.iter_mut() {
+        if material.is_added() || key.is_changed() {
+            let new_key = key.0.with_material_index(material_type.index);
+            if key.0 != new_key {
+                key.0 = new_key;
+            }
+        }
+    }
+}
+
+fn extract_outline_materials<M: OutlineMaterial>(
+    mut render_outlines: ResMut<RenderOutlineInstances>,
+    materials: Extract<Query<(Entity, &OutlineMaterialHandle<M>)>>,
+) {
+    for (entity, material) in materials.iter() {
+        if let Some(outline) = render_outlines.get_mut(&entity.into()) {
+            outline.material_id = Some(material.id().untyped());
+        }
+    }
+}
+
+fn prepare_outline_material_bind_groups<M: OutlineMaterial>(
+    materials: Res<RenderAssets<PreparedOutlineMaterial<M>>>,
+    mut bind_groups: ResMut<OutlineMaterialBindGroups>,
+) {
+    if !materials.is_changed() {
+        return;
+    }
+    bind_groups.retain(|id, _| id.type_id() != TypeId::of::<M>());
+    for (id, material) in materials.iter() {
+        bind_groups.insert(id.untyped(), material.bind_group.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Asset, AsBindGroup, Clone, TypePath)]
+    struct TestMaterialA {}
+
+    impl OutlineMaterial for TestMaterialA {}
+
+    #[derive(Asset, AsBindGroup, Clone, TypePath)]
+    struct TestMaterialB {}
+
+    impl OutlineMaterial for TestMaterialB {}
+
+    #[test]
+    fn test_material_indices() {
+        let mut indices = OutlineMaterialIndices::default();
+        assert_eq!(indices.allocate(), 1);
+        assert_eq!(indices.allocate(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many outline material types")]
+    fn test_material_indices_exhausted() {
+        let mut indices = OutlineMaterialIndices::default();
+        for _ in 0..1 << MATERIAL_INDEX_BITS {
+            indices.allocate();
+        }
+    }
+
+    #[test]
+    fn test_material_key() {
+        let mut app = App::new();
+        app.insert_resource(OutlineMaterialType::<TestMaterialA>::new(1))
+            .insert_resource(OutlineMaterialType::<TestMaterialB>::new(2))
+            .add_systems(
+                Update,
+                (
+                    compute_outline_material_key::<TestMaterialA>,
+                    compute_outline_material_key::<TestMaterialB>,
+                ),
+            );
+        let entity = app
+            .world_mut()
+            .spawn((
+                ComputedOutlineKey::default(),
+                OutlineMaterialHandle::<TestMaterialA>(Handle::default()),
+            ))
+            .id();
+        let material_index = |app: &App| {
+            app.world()
+                .get::<ComputedOutlineKey>(entity)
+                .unwrap()
+                .0
+                .material_index()
+        };
+
+        // Each material type sets its own index in the key.
+        app.update();
+        assert_eq!(material_index(&app), 1);
+
+        app.world_mut()
+            .entity_mut(entity)
+            .remove::<OutlineMaterialHandle<TestMaterialA>>()
+            .insert(OutlineMaterialHandle::<TestMaterialB>(Handle::default()));
+        app.update();
+        assert_eq!(material_index(&app), 2);
+
+        // Removing the material clears the index.
+        app.world_mut()
+            .entity_mut(entity)
+            .remove::<OutlineMaterialHandle<TestMaterialB>>();
+        app.update();
+        assert_eq!(material_index(&app), 0);
+    }
+}
@@ -14,7 +14,7 @@ use bevy::{
         render_resource::{
             CachedRenderPipelineId, Extent3d, LoadOp, Operations, PipelineCache,
             RenderPassColorAttachment, RenderPassDescriptor, SpecializedRenderPipelines, StoreOp,
-            TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+            TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
         },
         renderer::{RenderContext, RenderDevice, ViewQuery},
         sync_component::SyncComponent,
@@ -23,8 +23,10 @@ use bevy::{
     },
 };
 
+use crate::occlusion::ResolvedSceneDepth;
+use crate::render_target::OutlineTargetView;
 use crate::view_uniforms::OutlineViewUniform;
-use crate::OutlineMsaa;
+use crate::{OutlineMsaa, OutlinePassPlacement};
 
 #[derive(Component, Copy, Clone, Deref)]
 pub(crate) struct ResolvedOutlineMsaa(pub Msaa);
@@ -52,7 +54,13 @@ impl ExtractComponent for ResolvedOutlineMsaa {
 #[derive(Component)]
 pub(crate) struct OutlineViewTextures {
     pub color: Option<CachedTexture>,
+    /// Whether outlines are drawn to the main colour attachment of the view,
+    /// as only a separate depth texture is needed.
+    pub shared_color: bool,
     pub depth: ViewDepthTexture,
+    /// The image of an [`OutlineRenderTarget`](crate::OutlineRenderTarget),
+    /// which outlines are drawn into in place of the view target.
+    pub render_target: Option<TextureView>,
 }
 
 pub(crate) fn prepare_outline_view_textures(
@@ -64,14 +72,26 @@ pub(crate) fn prepare_outline_view_textures(
             Entity,
             &Msaa,
             &ResolvedOutlineMsaa,
+            Has<ResolvedSceneDepth>,
             &ExtractedCamera,
             &ViewTarget,
+            Option<&OutlineTargetView>,
+            Option<&OutlinePassPlacement>,
         ),
         With<OutlineViewUniform>,
     >,
 ) {
-    for (entity, msaa, resolved, camera, view_target) in views.iter() {
-        if resolved.0 == *msaa {
+    for (entity, msaa, resolved, scene_depth, camera, view_target, target_view, placement) in
+        views.iter()
+    {
+        // The scene depth texture is sampled when drawing outlines, so it
+        // can't also be used as the outline depth attachment. Nor can it be
+        // overwritten before post-processing such as depth of field and
+        // motion blur reads it.
+        let shared_color = resolved.0 == *msaa && target_view.is_none();
+        let pre_tonemap =
+            placement.copied().unwrap_or_default() == OutlinePassPlacement::PreTonemap;
+        if shared_color && !scene_depth && !pre_tonemap {
             commands.entity(entity).remove::<OutlineViewTextures>();
             continue;
         }
@@ -88,7 +108,7 @@ pub(crate) fn prepare_outline_view_textures(
         };
         let samples = resolved.samples();
 
-        let color = if samples > 1 {
+        let color = if samples > 1 && !shared_color {
             Some(texture_cache.get(
                 &render_device,
                 TextureDescriptor {
@@ -97,7 +117,7 @@ pub(crate) fn prepare_outline_view_textures(
                     mip_level_count: 1,
                     sample_count: samples,
                     dimension: TextureDimension::D2,
-                    format: view_target.main_texture_format(),
+                    format: target_view.map_or(view_target.main_texture_format(), |t| t.format),
                     usage: TextureUsages::RENDER_ATTACHMENT,
                     view_formats: &[],
                 },
@@ -122,7 +142,9 @@ pub(crate) fn prepare_outline_view_textures(
 
         commands.entity(entity).insert(OutlineViewTextures {
             color,
+            shared_color,
             depth: ViewDepthTexture::new(depth, Some(0.0)),
+            render_target: target_view.map(|t| t.view.clone()),
         });
     }
 }
@@ -190,10 +212,17 @@ pub(crate) fn prepare_msaa_extra_writeback_pipelines(
     pipeline_cache: Res<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<BlitPipeline>>,
     blit_pipeline: Res<BlitPipeline>,
-    view_targets: Query<(Entity, &ViewTarget, &ResolvedOutlineMsaa)>,
+    view_targets: Query<(
+        Entity,
+        &ViewTarget,
+        &ResolvedOutlineMsaa,
+        Has<OutlineTargetView>,
+    )>,
 ) {
-    for (entity, view_target, resolved) in view_targets.iter() {
-        if **resolved != Msaa::Off {
+    for (entity, view_target, resolved, has_target_view) in view_targets.iter() {
+        // Outlines drawn into a separate image start from a cleared texture
+        // rather than a copy of the scene
+        if **resolved != Msaa::Off && !has_target_view {
             let key = BlitPipelineKey {
                 target_format: view_target.main_texture_format(),
                 samples: resolved.0.samples(),
@@ -66,6 +66,7 @@ impl PhaseItemBatchSetKey for OutlineBatchSetKey {
 pub(crate) struct OutlineBinKey {
     pub asset_id: AssetId<Mesh>,
     pub texture_id: Option<AssetId<Image>>,
+    pub material_id: Option<UntypedAssetId>,
 }
 
 pub(crate) struct StencilOutline {
@@ -284,15 +285,91 @@ impl CachedRenderPipelinePhaseItem for TransparentOutline {
     }
 }
 
+pub(crate) struct OccludedOutline {
+    pub sorting_info: OutlineSortingInfo,
+    pub distance: f32,
+    pub pipeline: CachedRenderPipelineId,
+    pub entity: Entity,
+    pub main_entity: MainEntity,
+    pub draw_function: DrawFunctionId,
+    pub batch_range: Range<u32>,
+    pub extra_index: PhaseItemExtraIndex,
+    pub indexed: bool,
+}
+
+impl PhaseItem for OccludedOutline {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn main_entity(&self) -> bevy::render::sync_world::MainEntity {
+        self.main_entity
+    }
+
+    fn draw_function(&self) -> bevy::render::render_phase::DrawFunctionId {
+        self.draw_function
+    }
+
+    fn batch_range(&self) -> &Range<u32> {
+        &self.batch_range
+    }
+
+    fn batch_range_mut(&mut self) -> &mut Range<u32> {
+        &mut self.batch_range
+    }
+
+    fn extra_index(&self) -> PhaseItemExtraIndex {
+        self.extra_index.clone()
+    }
+
+    fn batch_range_and_extra_index_mut(&mut self) -> (&mut Range<u32>, &mut PhaseItemExtraIndex) {
+        (&mut self.batch_range, &mut self.extra_index)
+    }
+}
+
+impl SortedPhaseItem for OccludedOutline {
+    type SortKey = FloatOrd;
+
+    fn sort_key(&self) -> Self::SortKey {
+        FloatOrd(self.distance)
+    }
+
+    fn recalculate_sort_keys(
+        items: &mut IndexMap<(Entity, MainEntity), Self, EntityHash>,
+        view: &ExtractedView,
+    ) {
+        let rangefinder = OutlineRangefinder::new(view);
+        for item in items.values_mut() {
+            item.distance = rangefinder.distance_of(&item.sorting_info);
+        }
+    }
+
+    fn indexed(&self) -> bool {
+        self.indexed
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for OccludedOutline {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
 pub(crate) fn outline_colour_attachment<'a>(
     target: &'a ViewTarget,
     outline_textures: Option<&'a OutlineViewTextures>,
 ) -> RenderPassColorAttachment<'a> {
     match outline_textures {
-        Some(t) => {
+        Some(t) if !t.shared_color => {
+            let main_view = t
+                .render_target
+                .as_ref()
+                .unwrap_or(target.main_texture_view());
             let (view, resolve_target) = match &t.color {
-                Some(color) => (&color.default_view, Some(target.main_texture_view())),
-                None => (target.main_texture_view(), None),
+                Some(color) => (&color.default_view, Some(main_view)),
+                None => (main_view, None),
             };
             RenderPassColorAttachment {
                 view,
@@ -304,7 +381,7 @@ pub(crate) fn outline_colour_attachment<'a>(
                 },
             }
         }
-        None => target.get_color_attachment(),
+        _ => target.get_color_attachment(),
     }
 }
 
@@ -324,7 +401,7 @@ pub(crate) fn outline_render_pass(
     view: ViewQuery<(
         &ExtractedView,
         &ExtractedCamera,
-        &Camera3d,
+        Option<&Camera3d>,
         &ViewTarget,
         &ViewDepthTexture,
         &OutlineQueueStatus,
@@ -333,25 +410,48 @@ pub(crate) fn outline_render_pass(
     stencil_phases: Res<ViewBinnedRenderPhases<StencilOutline>>,
     opaque_phases: Res<ViewBinnedRenderPhases<OpaqueOutline>>,
     transparent_phases: Res<ViewSortedRenderPhases<TransparentOutline>>,
+    occluded_phases: Res<ViewSortedRenderPhases<OccludedOutline>>,
     mut render_context: RenderContext,
 ) {
     let view_entity = view.entity();
     let (view_extracted, camera, camera_3d, target, depth, queue_status, outline_textures) =
         view.into_inner();
 
-    let (Some(stencil_phase), Some(opaque_phase), Some(transparent_phase)) = (
+    let (Some(stencil_phase), Some(opaque_phase), Some(transparent_phase), Some(occluded_phase)) = (
         stencil_phases.get(&view_extracted.retained_view_entity),
         opaque_phases.get(&view_extracted.retained_view_entity),
         transparent_phases.get(&view_extracted.retained_view_entity),
+        occluded_phases.get(&view_extracted.retained_view_entity),
     ) else {
         return;
     };
 
+    // Clear the image of an outline render target before drawing into it
+    if outline_textures.is_some_and(|t| t.render_target.is_some()) {
+        let pass_descriptor = RenderPassDescriptor {
+            label: Some("outline_render_target_clear_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                ops: Operations {
+                    load: LoadOp::Clear(wgpu_types::Color::TRANSPARENT),
+                    store: StoreOp::Store,
+                },
+                ..outline_colour_attachment(target, outline_textures)
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        };
+        render_context.begin_tracked_render_pass(pass_descriptor);
+    }
+
     // If drawing anything, run stencil pass to clear the depth buffer
     if queue_status.has_volume {
         let depth_stencil_attachment = Some(RenderPassDepthStencilAttachment {
             depth_ops: Some(Operations {
-                load: camera_3d.depth_load_op.clone().into(),
+                load: camera_3d.map_or(LoadOp::Clear(0.0), |camera_3d| {
+                    camera_3d.depth_load_op.clone().into()
+                }),
                 store: StoreOp::Store,
             }),
             ..outline_depth_attachment(depth, outline_textures)
@@ -408,4 +508,22 @@ pub(crate) fn outline_render_pass(
             error!("Error encountered while rendering the outline transparent phase {err:?}");
         }
     }
+
+    if !occluded_phase.items.is_empty() {
+        let pass_descriptor = RenderPassDescriptor {
+            label: Some("outline_occluded_pass"),
+            color_attachments: &[Some(outline_colour_attachment(target, outline_textures))],
+            depth_stencil_attachment: Some(outline_depth_attachment(depth, outline_textures)),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        };
+        let mut tracked_pass = render_context.begin_tracked_render_pass(pass_descriptor);
+        if let Some(viewport) = camera.viewport.as_ref() {
+            tracked_pass.set_camera_viewport(viewport);
+        }
+        if let Err(err) = occluded_phase.render(&mut tracked_pass, world, view_entity) {
+            error!("Error encountered while rendering the outline occluded phase {err:?}");
+        }
+    }
 }
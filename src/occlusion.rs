@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use bevy::{
+    ecs::query::QueryItem,
+    render::{
+        extract_component::ExtractComponent,
+        render_resource::{
+            binding_types::{texture_depth_2d, texture_depth_2d_multisampled},
+            BindGroupLayoutEntryBuilder, TextureUsages,
+        },
+        sync_component::SyncComponent,
+        view::Msaa,
+    },
+};
+
+use crate::OutlineSceneOcclusion;
+
+/// The MSAA setting of the scene depth texture for views whose outlines are
+/// occluded by the scene.
+#[derive(Component, Copy, Clone, Deref)]
+pub(crate) struct ResolvedSceneDepth(pub Msaa);
+
+impl SyncComponent for ResolvedSceneDepth {
+    type Target = ResolvedSceneDepth;
+}
+
+impl ExtractComponent for ResolvedSceneDepth {
+    type QueryData = (&'static Msaa, Has<OutlineSceneOcclusion>);
+    type QueryFilter = With<Camera3d>;
+    type Out = ResolvedSceneDepth;
+
+    fn extract_component(
+        (msaa, scene_occlusion): QueryItem<'_, '_, Self::QueryData>,
+    ) -> Option<Self::Out> {
+        scene_occlusion.then_some(ResolvedSceneDepth(*msaa))
+    }
+}
+
+/// Ensures that the depth texture of cameras with scene occlusion enabled can
+/// be bound for sampling.
+pub(crate) fn enable_scene_depth_binding(
+    mut cameras: Query<&mut Camera3d, With<OutlineSceneOcclusion>>,
+) {
+    for mut camera_3d in cameras.iter_mut() {
+        let usages = TextureUsages::from(camera_3d.depth_texture_usages);
+        if !usages.contains(TextureUsages::TEXTURE_BINDING) {
+            camera_3d.depth_texture_usages = (usages | TextureUsages::TEXTURE_BINDING).into();
+        }
+    }
+}
+
+pub(crate) fn scene_depth_binding(multisampled: bool) -> BindGroupLayoutEntryBuilder {
+    if multisampled {
+        texture_depth_2d_multisampled()
+    } else {
+        texture_depth_2d()
+    }
+}
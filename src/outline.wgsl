@@ -2,16 +2,28 @@
 #import bevy_render::maths
 #import bevy_pbr::mesh_types::{SkinnedMesh, MorphAttributes, MorphDescriptor, MorphWeights}
 #import bevy_pbr::skinning::joint_matrices
-#import bevy_mod_outline::common::{OutlineViewUniform, VertexOutput, model_origin_z, outline_flat_depth}
+#import bevy_mod_outline::common::{OutlineViewUniform, VertexOutput, model_origin_z, outline_flat_depth, outline_logical_width, outline_distance_scaled_width, outline_distance_fade}
+#ifdef CUSTOM_EXTRUDE
+#import bevy_mod_outline::custom_extrude::{outline_extrude_direction, outline_extrude_offset}
+#else
+#import bevy_mod_outline::extrude::{outline_extrude_direction, outline_extrude_offset}
+#endif
 
 struct Instance {
     world_from_local: mat3x4<f32>,
     world_plane_origin: vec3<f32>,
     world_plane_offset: vec3<f32>,
     volume_colour: vec4<f32>,
+    occluded_colour: vec4<f32>,
+    distance_scaling: vec4<f32>,
+    alpha_mask_uv_rect: vec4<f32>,
+    fill_tile_size: vec2<f32>,
     volume_offset: f32,
+    occluded_offset: f32,
     stencil_offset: f32,
     alpha_mask_threshold: f32,
+    width_unit: u32,
+    distance_fade: f32,
     first_vertex_index: u32,
     current_skin_index: u32,
     current_morph_index: u32,
@@ -26,6 +38,12 @@ struct Vertex {
 #ifdef ALPHA_MASK_TEXTURE
     @location(2) uv: vec2<f32>,
 #endif
+#ifdef VERTEX_WIDTH
+    @location(3) outline_width: f32,
+#endif
+#ifdef VERTEX_COLOUR
+    @location(4) outline_colour: vec4<f32>,
+#endif
 #ifdef SKINNED
     @location(5) joint_indices: vec4<u32>,
     @location(6) joint_weights: vec4<f32>,
@@ -172,17 +190,36 @@ fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
     let model = bevy_render::maths::affine3_to_square(mesh[iid].world_from_local);
 #endif
     let clip_pos = view_uniform.clip_from_world * (model * vec4<f32>(vertex.position, 1.0));
-#ifdef VOLUME
+#ifdef OCCLUDED
+    let offset = mesh[iid].occluded_offset;
+#else ifdef VOLUME
     let offset = mesh[iid].volume_offset;
 #else
     let offset = mesh[iid].stencil_offset;
 #endif
+#ifdef VOLUME
+    let distance = length(mesh[iid].world_plane_origin - view_uniform.world_position);
+#endif
 #ifdef VERTEX_OFFSET_ZERO
     let out_xy = clip_pos.xy;
 #else
     let clip_norm = mat4to3(view_uniform.clip_from_world) * (mat4to3(model) * vertex.outline_normal);
-    let corrected_norm = normalize(clip_norm.xy * vec2<f32>(view_uniform.aspect, 1.0));
-    let ndc_delta = offset * corrected_norm * view_uniform.scale_clip_from_logical * clip_pos.w;
+    let corrected_norm = outline_extrude_direction(view_uniform, clip_pos, clip_norm);
+#ifdef VOLUME
+    let logical_offset = outline_distance_scaled_width(
+        outline_logical_width(view_uniform, offset, mesh[iid].width_unit, clip_pos.w),
+        mesh[iid].distance_scaling,
+        distance,
+    );
+#else
+    let logical_offset = outline_logical_width(view_uniform, offset, mesh[iid].width_unit, clip_pos.w);
+#endif
+#ifdef VERTEX_WIDTH
+    let vertex_offset = logical_offset * vertex.outline_width;
+#else
+    let vertex_offset = logical_offset;
+#endif
+    let ndc_delta = outline_extrude_offset(view_uniform, clip_pos, corrected_norm, vertex_offset);
     let out_xy = clip_pos.xy + ndc_delta;
 #endif
     var out: VertexOutput;
@@ -194,12 +231,25 @@ fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
     out.flat_depth = outline_flat_depth(view_uniform, mesh[iid].world_plane_origin, mesh[iid].world_plane_offset);
 #endif
 #endif
-#ifdef VOLUME
+#ifdef OCCLUDED
+    out.volume_colour = mesh[iid].occluded_colour;
+#else ifdef VOLUME
     out.volume_colour = mesh[iid].volume_colour;
 #endif
+#ifdef VERTEX_COLOUR
+    out.volume_colour *= vertex.outline_colour;
+#endif
+#ifdef VOLUME
+    out.volume_colour.a *= outline_distance_fade(mesh[iid].distance_scaling, mesh[iid].distance_fade, distance);
+#endif
+#ifdef FILL_TEXTURE
+    // Tile the fill texture in screen space, where the fragment position is
+    // in physical pixels.
+    out.fill_scale = 1.0 / (mesh[iid].fill_tile_size * view_uniform.scale_physical_from_logical);
+#endif
 #ifdef ALPHA_MASK_TEXTURE
     out.alpha_mask_threshold = mesh[iid].alpha_mask_threshold;
-    out.uv = vertex.uv;
+    out.uv = mesh[iid].alpha_mask_uv_rect.xy + vertex.uv * mesh[iid].alpha_mask_uv_rect.zw;
 #endif
     return out;
 }
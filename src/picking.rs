@@ -0,0 +1,507 @@
+use bevy::ecs::query::{QueryData, QueryItem};
+use bevy::picking::events::{Click, Out, Over, Pointer};
+use bevy::picking::pointer::PointerButton;
+use bevy::prelude::*;
+
+use crate::computed::compute_outline;
+use crate::OutlineVolume;
+
+/// A component for styling the outline of an entity while it is hovered by a
+/// pointer.
+///
+/// Requires the [`OutlinePickingPlugin`]. The [`OutlineVolume`] of the entity
+/// is replaced by this style while the entity is hovered, and restored
+/// afterwards. Any changes made to the [`OutlineVolume`] while it is replaced
+/// are lost when it is restored, so change the style instead. Pointer events
+/// bubble up from descendants, so adding this to the root of a hierarchy
+/// together with [`PropagateOutline`](crate::PropagateOutline) outlines the
+/// whole hierarchy when any part of it is hovered.
+#[derive(Clone, Component, Deref, DerefMut)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct HoverOutline(pub OutlineVolume);
+
+/// A component for styling the outline of an entity while it is selected.
+///
+/// Requires the [`OutlinePickingPlugin`]. Clicking an entity with this
+/// component, or any descendant without one, selects it, adding
+/// [`OutlineSelected`]. The selection style
+/// takes precedence over any [`HoverOutline`], and otherwise behaves in the
+/// same way.
+#[derive(Clone, Component, Deref, DerefMut)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct SelectOutline(pub OutlineVolume);
+
+/// A marker component for entities with a [`HoverOutline`] which are
+/// currently hovered by a pointer.
+#[derive(Clone, Component, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineHovered;
+
+/// A marker component for entities with a [`SelectOutline`] which are
+/// currently selected.
+///
+/// This may also be added or removed directly to change the selection.
+#[derive(Clone, Component, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct OutlineSelected;
+
+/// A resource for configuring how the [`OutlinePickingPlugin`] responds to
+/// clicks.
+#[derive(Clone, Resource)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct OutlinePickingSettings {
+    /// Keys which, while held, make clicking an entity toggle its selection
+    /// instead of replacing the existing selection.
+    pub multi_select_keys: Vec<KeyCode>,
+    /// Deselect all entities when a click hits nothing selectable.
+    pub deselect_on_miss: bool,
+}
+
+impl Default for OutlinePickingSettings {
+    fn default() -> Self {
+        OutlinePickingSettings {
+            multi_select_keys: vec![
+                KeyCode::ShiftLeft,
+                KeyCode::ShiftRight,
+                KeyCode::ControlLeft,
+                KeyCode::ControlRight,
+            ],
+            deselect_on_miss: true,
+        }
+    }
+}
+
+/// Adds support for outlining entities when they are hovered or selected
+/// using Bevy's picking. See [`HoverOutline`] and [`SelectOutline`].
+pub struct OutlinePickingPlugin;
+
+impl Plugin for OutlinePickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OutlinePickingSettings>()
+            .add_observer(hover_on_over)
+            .add_observer(unhover_on_out)
+            .add_observer(select_on_click)
+            .add_observer(on_add_hovered)
+            .add_observer(on_remove_hovered)
+            .add_observer(on_add_selected)
+            .add_observer(on_remove_selected)
+            .add_systems(
+                PostUpdate,
+                update_picking_outline_styles.before(compute_outline),
+            );
+
+        #[cfg(feature = "reflect")]
+        app.register_type::<HoverOutline>()
+            .register_type::<SelectOutline>()
+            .register_type::<OutlineHovered>()
+            .register_type::<OutlineSelected>()
+            .register_type::<OutlinePickingSettings>();
+    }
+}
+
+/// The outline volume of an entity before it was replaced by a picking style.
+#[derive(Component)]
+struct PickingBaseOutline(OutlineVolume);
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct PickingOutlineData {
+    hover: Option<&'static HoverOutline>,
+    select: Option<&'static SelectOutline>,
+    hovered: Has<OutlineHovered>,
+    selected: Has<OutlineSelected>,
+    volume: Option<&'static mut OutlineVolume>,
+    base: Option<&'static PickingBaseOutline>,
+}
+
+/// Applies the style matching the hovered and selected state of an entity,
+/// saving its own outline volume when first replaced and restoring it when
+/// no style applies.
+fn apply_picking_outline(
+    commands: &mut Commands,
+    entity: Entity,
+    data: QueryItem<'_, '_, PickingOutlineData>,
+) {
+    let PickingOutlineDataItem {
+        hover,
+        select,
+        hovered,
+        selected,
+        volume,
+        base,
+    } = data;
+    let style = match (hover, select) {
+        (_, Some(select)) if selected => Some(&select.0),
+        (Some(hover), _) if hovered => Some(&hover.0),
+        _ => None,
+    };
+    let new_volume = match (style, base) {
+        (Some(style), Some(_)) => style.clone(),
+        (Some(style), None) => {
+            let base = volume.as_deref().cloned().unwrap_or_default();
+            commands.entity(entity).try_insert(PickingBaseOutline(base));
+            style.clone()
+        }
+        (None, Some(base)) => {
+            commands.entity(entity).try_remove::<PickingBaseOutline>();
+            base.0.clone()
+        }
+        (None, None) => return,
+    };
+    match volume {
+        Some(mut volume) => *volume = new_volume,
+        None => {
+            commands.entity(entity).try_insert(new_volume);
+        }
+    }
+}
+
+fn hover_on_over(
+    over: On<Pointer<Over>>,
+    mut commands: Commands,
+    query: Query<(), With<HoverOutline>>,
+) {
+    if query.contains(over.entity) {
+        commands.entity(over.entity).try_insert(OutlineHovered);
+    }
+}
+
+fn unhover_on_out(
+    out: On<Pointer<Out>>,
+    mut commands: Commands,
+    query: Query<(), With<OutlineHovered>>,
+) {
+    if query.contains(out.entity) {
+        commands.entity(out.entity).try_remove::<OutlineHovered>();
+    }
+}
+
+fn select_on_click(
+    click: On<Pointer<Click>>,
+    mut commands: Commands,
+    selectable: Query<Has<OutlineSelected>, With<SelectOutline>>,
+    parents: Query<&ChildOf>,
+    selected: Query<Entity, With<OutlineSelected>>,
+    settings: Res<OutlinePickingSettings>,
+    keys: Option<Res<ButtonInput<KeyCode>>>,
+) {
+    // Clicks bubble up through the ancestors of the entity clicked, so only
+    // respond once, to the original target.
+    if click.button != PointerButton::Primary || click.entity != click.original_event_target() {
+        return;
+    }
+    let multi_select =
+        keys.is_some_and(|keys| keys.any_pressed(settings.multi_select_keys.iter().copied()));
+
+    // Select the nearest selectable entity in the hierarchy clicked. Clicks
+    // which find none are misses, including those which hit nothing at all
+    // and so target the window.
+    let target = std::iter::once(click.entity)
+        .chain(parents.iter_ancestors(click.entity))
+        .find_map(|entity| {
+            selectable
+                .get(entity)
+                .ok()
+                .map(|is_selected| (entity, is_selected))
+        });
+    match target {
+        Some((target, is_selected)) => {
+            if multi_select && is_selected {
+                commands.entity(target).try_remove::<OutlineSelected>();
+                return;
+            }
+            if !multi_select {
+                for entity in selected.iter().filter(|entity| *entity != target) {
+                    commands.entity(entity).try_remove::<OutlineSelected>();
+                }
+            }
+            commands.entity(target).try_insert(OutlineSelected);
+        }
+        None if settings.deselect_on_miss && !multi_select => {
+            for entity in selected.iter() {
+                commands.entity(entity).try_remove::<OutlineSelected>();
+            }
+        }
+        None => {}
+    }
+}
+
+fn on_add_hovered(
+    add: On<Add, OutlineHovered>,
+    mut commands: Commands,
+    mut query: Query<PickingOutlineData>,
+) {
+    if let Ok(data) = query.get_mut(add.entity) {
+        apply_picking_outline(&mut commands, add.entity, data);
+    }
+}
+
+fn on_remove_hovered(
+    remove: On<Remove, OutlineHovered>,
+    mut commands: Commands,
+    mut query: Query<PickingOutlineData>,
+) {
+    if let Ok(mut data) = query.get_mut(remove.entity) {
+        // The component is still present while its removal is observed.
+        data.hovered = false;
+        apply_picking_outline(&mut commands, remove.entity, data);
+    }
+}
+
+fn on_add_selected(
+    add: On<Add, OutlineSelected>,
+    mut commands: Commands,
+    mut query: Query<PickingOutlineData>,
+) {
+    if let Ok(data) = query.get_mut(add.entity) {
+        apply_picking_outline(&mut commands, add.entity, data);
+    }
+}
+
+fn on_remove_selected(
+    remove: On<Remove, OutlineSelected>,
+    mut commands: Commands,
+    mut query: Query<PickingOutlineData>,
+) {
+    if let Ok(mut data) = query.get_mut(remove.entity) {
+        data.selected = false;
+        apply_picking_outline(&mut commands, remove.entity, data);
+    }
+}
+
+/// Reapplies the styles of hovered and selected entities when they change.
+#[allow(clippy::type_complexity)]
+fn update_picking_outline_styles(
+    mut commands: Commands,
+    mut query: Query<
+        (Entity, PickingOutlineData),
+        (
+            Or<(With<OutlineHovered>, With<OutlineSelected>)>,
+            Or<(Changed<HoverOutline>, Changed<SelectOutline>)>,
+        ),
+    >,
+) {
+    for (entity, data) in query.iter_mut() {
+        apply_picking_outline(&mut commands, entity, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::camera::{ManualTextureViewHandle, NormalizedRenderTarget};
+    use bevy::picking::backend::HitData;
+    use bevy::picking::pointer::{Location, PointerId};
+
+    use super::*;
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, OutlinePickingPlugin));
+        app
+    }
+
+    fn pointer<E: std::fmt::Debug + Clone + Reflect>(entity: Entity, event: E) -> Pointer<E> {
+        Pointer {
+            entity,
+            pointer_id: PointerId::Mouse,
+            pointer_location: Location {
+                target: NormalizedRenderTarget::TextureView(ManualTextureViewHandle(0)),
+                position: Vec2::ZERO,
+            },
+            event,
+        }
+    }
+
+    fn hit() -> HitData {
+        HitData::new(Entity::PLACEHOLDER, 0.0, None, None)
+    }
+
+    fn click(app: &mut App, entity: Entity) {
+        app.world_mut().trigger(pointer(
+            entity,
+            Click {
+                button: PointerButton::Primary,
+                hit: hit(),
+                duration: Duration::ZERO,
+            },
+        ));
+        app.update();
+    }
+
+    fn volume(colour: Color) -> OutlineVolume {
+        OutlineVolume {
+            visible: true,
+            width: 1.0,
+            colour,
+            ..default()
+        }
+    }
+
+    fn colour(app: &App, entity: Entity) -> Color {
+        app.world().get::<OutlineVolume>(entity).unwrap().colour
+    }
+
+    fn is_selected(app: &App, entity: Entity) -> bool {
+        app.world().entity(entity).contains::<OutlineSelected>()
+    }
+
+    #[test]
+    fn test_hover() {
+        let mut app = setup();
+        let entity = app
+            .world_mut()
+            .spawn((volume(Color::WHITE), HoverOutline(volume(Color::BLACK))))
+            .id();
+        let child = app.world_mut().spawn(ChildOf(entity)).id();
+
+        // Hovering a descendant bubbles up to the entity
+        app.world_mut().trigger(pointer(child, Over { hit: hit() }));
+        app.update();
+        assert!(app.world().entity(entity).contains::<OutlineHovered>());
+        assert!(!app.world().entity(child).contains::<OutlineHovered>());
+        assert_eq!(colour(&app, entity), Color::BLACK);
+
+        app.world_mut().trigger(pointer(child, Out { hit: hit() }));
+        app.update();
+        assert!(!app.world().entity(entity).contains::<OutlineHovered>());
+        assert!(!app.world().entity(entity).contains::<PickingBaseOutline>());
+        assert_eq!(colour(&app, entity), Color::WHITE);
+    }
+
+    #[test]
+    fn test_hover_without_volume() {
+        let mut app = setup();
+        let entity = app
+            .world_mut()
+            .spawn(HoverOutline(volume(Color::BLACK)))
+            .id();
+
+        app.world_mut()
+            .trigger(pointer(entity, Over { hit: hit() }));
+        app.update();
+        assert_eq!(colour(&app, entity), Color::BLACK);
+
+        app.world_mut().trigger(pointer(entity, Out { hit: hit() }));
+        app.update();
+        assert!(!app.world().get::<OutlineVolume>(entity).unwrap().visible);
+    }
+
+    #[test]
+    fn test_select() {
+        let mut app = setup();
+        let a = app
+            .world_mut()
+            .spawn((volume(Color::WHITE), SelectOutline(volume(Color::BLACK))))
+            .id();
+        let a_child = app.world_mut().spawn(ChildOf(a)).id();
+        let b = app
+            .world_mut()
+            .spawn((volume(Color::WHITE), SelectOutline(volume(Color::BLACK))))
+            .id();
+        let other = app.world_mut().spawn(volume(Color::WHITE)).id();
+
+        click(&mut app, a);
+        assert!(is_selected(&app, a));
+        assert_eq!(colour(&app, a), Color::BLACK);
+
+        // Selection replaces the existing selection
+        click(&mut app, b);
+        assert!(!is_selected(&app, a));
+        assert!(is_selected(&app, b));
+        assert_eq!(colour(&app, a), Color::WHITE);
+        assert_eq!(colour(&app, b), Color::BLACK);
+
+        // Clicking a descendant selects its selectable ancestor
+        click(&mut app, a_child);
+        assert!(is_selected(&app, a));
+        assert!(!is_selected(&app, a_child));
+        assert!(!is_selected(&app, b));
+
+        // Clicking an entity which is not selectable is a miss
+        click(&mut app, other);
+        assert!(!is_selected(&app, a));
+        assert!(!is_selected(&app, other));
+        assert_eq!(colour(&app, a), Color::WHITE);
+        assert_eq!(colour(&app, other), Color::WHITE);
+    }
+
+    #[test]
+    fn test_select_on_miss_disabled() {
+        let mut app = setup();
+        app.world_mut()
+            .resource_mut::<OutlinePickingSettings>()
+            .deselect_on_miss = false;
+        let entity = app
+            .world_mut()
+            .spawn((volume(Color::WHITE), SelectOutline(volume(Color::BLACK))))
+            .id();
+        let other = app.world_mut().spawn_empty().id();
+
+        click(&mut app, entity);
+        click(&mut app, other);
+        assert!(is_selected(&app, entity));
+    }
+
+    #[test]
+    fn test_multi_select() {
+        let mut app = setup();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        let a = app
+            .world_mut()
+            .spawn((volume(Color::WHITE), SelectOutline(volume(Color::BLACK))))
+            .id();
+        let b = app
+            .world_mut()
+            .spawn((volume(Color::WHITE), SelectOutline(volume(Color::BLACK))))
+            .id();
+
+        click(&mut app, a);
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ShiftLeft);
+        click(&mut app, b);
+        assert!(is_selected(&app, a));
+        assert!(is_selected(&app, b));
+
+        // Clicking a selected entity toggles it off
+        click(&mut app, a);
+        assert!(!is_selected(&app, a));
+        assert!(is_selected(&app, b));
+        assert_eq!(colour(&app, a), Color::WHITE);
+    }
+
+    #[test]
+    fn test_selection_overrides_hover() {
+        let mut app = setup();
+        let entity = app
+            .world_mut()
+            .spawn((
+                volume(Color::WHITE),
+                HoverOutline(volume(Color::BLACK)),
+                SelectOutline(volume(Color::srgb(1.0, 0.0, 0.0))),
+            ))
+            .id();
+
+        app.world_mut()
+            .trigger(pointer(entity, Over { hit: hit() }));
+        click(&mut app, entity);
+        assert_eq!(colour(&app, entity), Color::srgb(1.0, 0.0, 0.0));
+
+        app.world_mut()
+            .entity_mut(entity)
+            .remove::<OutlineSelected>();
+        app.update();
+        assert_eq!(colour(&app, entity), Color::BLACK);
+
+        app.world_mut().trigger(pointer(entity, Out { hit: hit() }));
+        app.update();
+        assert_eq!(colour(&app, entity), Color::WHITE);
+    }
+}
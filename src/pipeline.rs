@@ -1,21 +1,23 @@
 use std::borrow::Cow;
 
 use bevy::asset::uuid_handle;
-use bevy::ecs::system::lifetimeless::SRes;
+use bevy::ecs::system::lifetimeless::{Read, SQuery, SRes};
 use bevy::ecs::system::SystemParamItem;
 use bevy::mesh::MeshVertexBufferLayoutRef;
 use bevy::pbr::{
     setup_morph_and_skinning_defs, skins_use_uniform_buffers, MorphIndices, SkinUniforms,
 };
+use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
 use bevy::render::batching::{gpu_preprocessing, GetBatchData, GetFullBatchData};
 use bevy::render::mesh::allocator::{MeshAllocator, MeshSlabs};
 use bevy::render::render_resource::binding_types::{sampler, texture_2d, uniform_buffer_sized};
 use bevy::render::render_resource::{
-    BindGroupLayoutDescriptor, BindGroupLayoutEntries, BlendState, ColorTargetState, ColorWrites,
-    CompareFunction, DepthBiasState, DepthStencilState, Face, FragmentState, FrontFace,
-    GpuArrayBuffer, MultisampleState, PolygonMode, PrimitiveState, SamplerBindingType,
-    ShaderStages, ShaderType, StencilState, TextureFormat, TextureSampleType, VertexState,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntries, BlendComponent, BlendFactor, BlendOperation,
+    BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState,
+    Face, FragmentState, FrontFace, GpuArrayBuffer, MultisampleState, PolygonMode, PrimitiveState,
+    SamplerBindingType, ShaderStages, ShaderType, StencilState, TextureFormat, TextureSampleType,
+    VertexState,
 };
 use bevy::render::renderer::RenderDevice;
 use bevy::render::settings::{Backends, WgpuSettings};
@@ -29,10 +31,14 @@ use bevy::{
 };
 use nonmax::NonMaxU32;
 
+use crate::material::OutlineMaterialSpecialisation;
+use crate::occlusion::scene_depth_binding;
 use crate::pipeline_key::{DerivedPipelineKey, PassType};
-use crate::uniforms::{DepthMode, OutlineInstanceUniform, RenderOutlineInstances};
+use crate::uniforms::{
+    DepthMode, OutlineInstanceUniform, OutlineRingIndex, RenderOutlineInstances,
+};
 use crate::view_uniforms::OutlineViewUniform;
-use crate::ATTRIBUTE_OUTLINE_NORMAL;
+use crate::{ATTRIBUTE_OUTLINE_COLOUR, ATTRIBUTE_OUTLINE_NORMAL, ATTRIBUTE_OUTLINE_WIDTH};
 
 pub(crate) const COMMON_SHADER_HANDLE: Handle<Shader> =
     uuid_handle!("aee41cd9-fc8f-4788-9ea4-f85bd8070c65");
@@ -43,12 +49,22 @@ pub(crate) const OUTLINE_SHADER_HANDLE: Handle<Shader> =
 pub(crate) const FRAGMENT_SHADER_HANDLE: Handle<Shader> =
     uuid_handle!("1f5b5967-7cbb-4392-8f34-421587938a12");
 
+pub(crate) const SCENE_DEPTH_SHADER_HANDLE: Handle<Shader> =
+    uuid_handle!("5d0c8e7a-3b64-4f1e-9a2d-c7e1b58f9043");
+
+pub(crate) const EXTRUDE_SHADER_HANDLE: Handle<Shader> =
+    uuid_handle!("a58fdfa1-e804-4ddf-aebe-01e43c1e72f1");
+
 #[derive(Resource)]
 pub(crate) struct OutlinePipeline {
     mesh_pipeline: MeshPipeline,
     pub outline_view_bind_group_layout: BindGroupLayoutDescriptor,
+    pub outline_view_scene_depth_bind_group_layouts: [BindGroupLayoutDescriptor; 2],
     pub outline_instance_bind_group_layout: BindGroupLayoutDescriptor,
     pub alpha_mask_bind_group_layout: BindGroupLayoutDescriptor,
+    pub fill_texture_bind_group_layout: BindGroupLayoutDescriptor,
+    pub mesh_2d_bind_group_layout: BindGroupLayoutDescriptor,
+    pub materials: HashMap<u32, OutlineMaterialSpecialisation>,
     pub instance_batch_size: Option<u32>,
     pub skins_use_uniform_buffers: bool,
 }
@@ -66,6 +82,18 @@ pub(crate) fn init_outline_pipeline(
             uniform_buffer_sized(true, Some(OutlineViewUniform::min_size())),
         ),
     );
+    let outline_view_scene_depth_bind_group_layouts = [false, true].map(|multisampled| {
+        BindGroupLayoutDescriptor::new(
+            "outline_view_scene_depth_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::VERTEX_FRAGMENT,
+                (
+                    uniform_buffer_sized(true, Some(OutlineViewUniform::min_size())),
+                    scene_depth_binding(multisampled),
+                ),
+            ),
+        )
+    });
     let outline_instance_bind_group_layout = BindGroupLayoutDescriptor::new(
         "outline_instance_bind_group_layout",
         &BindGroupLayoutEntries::single(
@@ -83,19 +111,53 @@ pub(crate) fn init_outline_pipeline(
             ),
         ),
     );
+    let fill_texture_bind_group_layout = BindGroupLayoutDescriptor::new(
+        "fill_texture_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+            ),
+        ),
+    );
+    // 2D meshes have neither skins nor morph targets, so their mesh bind
+    // group is empty
+    let mesh_2d_bind_group_layout =
+        BindGroupLayoutDescriptor::new("outline_mesh_2d_bind_group_layout", &[]);
 
     let instance_batch_size = GpuArrayBuffer::<OutlineInstanceUniform>::batch_size(&limits);
     let skins_use_uniform_buffers = skins_use_uniform_buffers(&limits);
     commands.insert_resource(OutlinePipeline {
         mesh_pipeline: mesh_pipeline.clone(),
         outline_view_bind_group_layout,
+        outline_view_scene_depth_bind_group_layouts,
         outline_instance_bind_group_layout,
         alpha_mask_bind_group_layout,
+        fill_texture_bind_group_layout,
+        mesh_2d_bind_group_layout,
+        materials: HashMap::default(),
         instance_batch_size,
         skins_use_uniform_buffers,
     });
 }
 
+impl OutlinePipeline {
+    /// Returns the layout of the view bind group, which also holds the scene
+    /// depth texture for views with scene occlusion enabled.
+    pub(crate) fn view_layout(
+        &self,
+        scene_depth: bool,
+        multisampled: bool,
+    ) -> &BindGroupLayoutDescriptor {
+        if scene_depth {
+            &self.outline_view_scene_depth_bind_group_layouts[multisampled as usize]
+        } else {
+            &self.outline_view_bind_group_layout
+        }
+    }
+}
+
 impl SpecializedMeshPipeline for OutlinePipeline {
     type Key = DerivedPipelineKey;
 
@@ -108,22 +170,47 @@ impl SpecializedMeshPipeline for OutlinePipeline {
         let mut vertex_defs = vec![];
         let mut fragment_defs = vec![];
         let mut buffer_attrs = vec![Mesh::ATTRIBUTE_POSITION.at_shader_location(0)];
+        // Stencil and flood keys never carry a material index
+        let material = self.materials.get(&key.material_index());
 
         let bind_layouts = vec![
-            self.outline_view_bind_group_layout.clone(),
+            self.view_layout(key.scene_depth(), key.scene_depth_multisampled())
+                .clone(),
             self.outline_instance_bind_group_layout.clone(),
-            setup_morph_and_skinning_defs(
-                &self.mesh_pipeline.mesh_layouts,
-                layout,
-                5,
-                &key.into(),
-                &mut vertex_defs,
-                &mut buffer_attrs,
-                self.skins_use_uniform_buffers,
-            ),
-            self.alpha_mask_bind_group_layout.clone(),
+            if key.mesh_2d() {
+                self.mesh_2d_bind_group_layout.clone()
+            } else {
+                setup_morph_and_skinning_defs(
+                    &self.mesh_pipeline.mesh_layouts,
+                    layout,
+                    5,
+                    &key.into(),
+                    &mut vertex_defs,
+                    &mut buffer_attrs,
+                    self.skins_use_uniform_buffers,
+                )
+            },
+            // Only volume passes use a fill texture or material and they never
+            // use an alpha mask, so these share a bind group
+            if let Some(material) = material {
+                material.layout.clone()
+            } else if key.fill_texture() {
+                self.fill_texture_bind_group_layout.clone()
+            } else {
+                self.alpha_mask_bind_group_layout.clone()
+            },
         ];
 
+        if material.is_some() {
+            let val = ShaderDefVal::from("OUTLINE_MATERIAL");
+            vertex_defs.push(val.clone());
+            fragment_defs.push(val);
+        } else if key.fill_texture() {
+            let val = ShaderDefVal::from("FILL_TEXTURE");
+            vertex_defs.push(val.clone());
+            fragment_defs.push(val);
+        }
+
         if key.alpha_mask_texture() {
             let val = ShaderDefVal::from("ALPHA_MASK_TEXTURE");
             vertex_defs.push(val.clone());
@@ -148,7 +235,7 @@ impl SpecializedMeshPipeline for OutlinePipeline {
         if key.msaa() != Msaa::Off {
             fragment_defs.push(ShaderDefVal::from("MSAA"));
         }
-        if let (PassType::Stencil | PassType::Volume, DepthMode::Flat) =
+        if let (PassType::Stencil | PassType::Volume | PassType::OccludedVolume, DepthMode::Flat) =
             (key.pass_type(), key.depth_mode())
         {
             let val = ShaderDefVal::from("FLAT_DEPTH");
@@ -160,7 +247,7 @@ impl SpecializedMeshPipeline for OutlinePipeline {
         }
         let cull_mode = match (key.pass_type(), key.depth_mode()) {
             (PassType::Stencil, DepthMode::Real) => Some(Face::Back),
-            (PassType::Volume, DepthMode::Real) => Some(Face::Front),
+            (PassType::Volume | PassType::OccludedVolume, DepthMode::Real) => Some(Face::Front),
             _ => {
                 if key.double_sided() {
                     None
@@ -180,16 +267,41 @@ impl SpecializedMeshPipeline for OutlinePipeline {
                 }
                 .at_shader_location(1),
             );
+            if key.vertex_width() {
+                vertex_defs.push(ShaderDefVal::from("VERTEX_WIDTH"));
+                buffer_attrs.push(ATTRIBUTE_OUTLINE_WIDTH.at_shader_location(3));
+            }
+        }
+        // Stencil and flood keys never use vertex colours
+        if key.vertex_colour() {
+            let val = ShaderDefVal::from("VERTEX_COLOUR");
+            vertex_defs.push(val.clone());
+            fragment_defs.push(val);
+            buffer_attrs.push(ATTRIBUTE_OUTLINE_COLOUR.at_shader_location(4));
         }
         if key.plane_offset_zero() {
             vertex_defs.push(ShaderDefVal::from("PLANE_OFFSET_ZERO"));
         }
+        if key.custom_extrude() {
+            vertex_defs.push(ShaderDefVal::from("CUSTOM_EXTRUDE"));
+        }
         match key.pass_type() {
             PassType::Stencil => {}
-            PassType::Volume => {
+            PassType::Volume | PassType::OccludedVolume => {
                 let val = ShaderDefVal::from("VOLUME");
                 vertex_defs.push(val.clone());
                 fragment_defs.push(val);
+                if key.pass_type() == PassType::OccludedVolume {
+                    let val = ShaderDefVal::from("OCCLUDED");
+                    vertex_defs.push(val.clone());
+                    fragment_defs.push(val);
+                }
+                if key.scene_depth() {
+                    fragment_defs.push(ShaderDefVal::from("SCENE_DEPTH"));
+                    if key.scene_depth_multisampled() {
+                        fragment_defs.push(ShaderDefVal::from("SCENE_DEPTH_MULTISAMPLED"));
+                    }
+                }
                 targets.push(Some(ColorTargetState {
                     format: key.target_format(),
                     blend: Some(if key.transparent() {
@@ -205,25 +317,39 @@ impl SpecializedMeshPipeline for OutlinePipeline {
                 let val = ShaderDefVal::from("FLOOD_INIT");
                 vertex_defs.push(val.clone());
                 fragment_defs.push(val);
+                // Max blending keeps the nearest seed depth where the mesh
+                // overlaps itself, as there is no depth buffer in this pass.
+                let nearest = BlendComponent {
+                    src_factor: BlendFactor::One,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Max,
+                };
                 targets.push(Some(ColorTargetState {
                     format: if key.msaa() != Msaa::Off {
-                        TextureFormat::R8Unorm
-                    } else {
                         TextureFormat::Rg16Float
+                    } else {
+                        TextureFormat::Rgba16Float
                     },
-                    blend: Some(BlendState::REPLACE),
+                    blend: Some(BlendState {
+                        color: nearest,
+                        alpha: nearest,
+                    }),
                     write_mask: ColorWrites::ALL,
                 }));
             }
         }
+        // Occluded volumes are still tested against the outline depth so that
+        // stencils apply, with the scene depth test inverted in the shader.
         let depth_stencil = match key.pass_type() {
-            PassType::Stencil | PassType::Volume => Some(DepthStencilState {
-                format: TextureFormat::Depth32Float,
-                depth_write_enabled: Some(true),
-                depth_compare: Some(CompareFunction::Greater),
-                stencil: StencilState::default(),
-                bias: DepthBiasState::default(),
-            }),
+            PassType::Stencil | PassType::Volume | PassType::OccludedVolume => {
+                Some(DepthStencilState {
+                    format: TextureFormat::Depth32Float,
+                    depth_write_enabled: Some(true),
+                    depth_compare: Some(CompareFunction::Greater),
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                })
+            }
             #[cfg(feature = "flood")]
             PassType::FloodInit => None,
         };
@@ -236,13 +362,17 @@ impl SpecializedMeshPipeline for OutlinePipeline {
         };
         Ok(RenderPipelineDescriptor {
             vertex: VertexState {
-                shader: OUTLINE_SHADER_HANDLE,
+                shader: material
+                    .and_then(|material| material.vertex_shader.clone())
+                    .unwrap_or(OUTLINE_SHADER_HANDLE),
                 entry_point: None,
                 shader_defs: vertex_defs,
                 buffers,
             },
             fragment: Some(FragmentState {
-                shader: FRAGMENT_SHADER_HANDLE,
+                shader: material
+                    .and_then(|material| material.fragment_shader.clone())
+                    .unwrap_or(FRAGMENT_SHADER_HANDLE),
                 shader_defs: fragment_defs,
                 entry_point: None,
                 targets,
@@ -274,6 +404,8 @@ impl SpecializedMeshPipeline for OutlinePipeline {
 pub(crate) struct OutlineBatchSetCompareData {
     mesh_slabs: Option<MeshSlabs>,
     alpha_mask_id: Option<AssetId<Image>>,
+    fill_texture_id: Option<AssetId<Image>>,
+    material_id: Option<UntypedAssetId>,
 }
 
 impl GetBatchData for OutlinePipeline {
@@ -282,22 +414,24 @@ impl GetBatchData for OutlinePipeline {
         SRes<MeshAllocator>,
         SRes<SkinUniforms>,
         SRes<MorphIndices>,
+        SQuery<Read<OutlineRingIndex>>,
     );
     type BatchSetCompareData = OutlineBatchSetCompareData;
     type BatchCompareData = AssetId<Mesh>;
     type BufferData = OutlineInstanceUniform;
 
     fn get_batch_data(
-        (render_outlines, mesh_allocator, skin_uniforms, morph_indices): &SystemParamItem<
+        (render_outlines, mesh_allocator, skin_uniforms, morph_indices, rings): &SystemParamItem<
             Self::Param,
         >,
-        (_entity, main_entity): (Entity, MainEntity),
+        (entity, main_entity): (Entity, MainEntity),
     ) -> Option<(
         Self::BufferData,
         Option<(Self::BatchSetCompareData, Self::BatchCompareData)>,
     )> {
         let outline = render_outlines.get(&main_entity)?;
-        let instance_data = outline.instance_data.prepare_instance(
+        let ring = rings.get(entity).ok().map(|ring| ring.0);
+        let instance_data = outline.instance_data_for(ring).prepare_instance(
             &outline.mesh_id,
             main_entity,
             mesh_allocator,
@@ -305,12 +439,15 @@ impl GetBatchData for OutlinePipeline {
             morph_indices,
         );
 
-        // Only batch entities with the same mesh and alpha mask texture
+        // Only batch entities with the same mesh, alpha mask, fill texture,
+        // and material
         let batch_data = if outline.automatic_batching {
             Some((
                 OutlineBatchSetCompareData {
                     mesh_slabs: mesh_allocator.mesh_slabs(&outline.mesh_id),
                     alpha_mask_id: outline.alpha_mask_id,
+                    fill_texture_id: outline.fill_texture_id,
+                    material_id: outline.material_id,
                 },
                 outline.mesh_id,
             ))
@@ -326,7 +463,7 @@ impl GetFullBatchData for OutlinePipeline {
     type BufferInputData = ();
 
     fn get_binned_batch_data(
-        (render_outlines, mesh_allocator, skin_uniforms, morph_indices): &SystemParamItem<
+        (render_outlines, mesh_allocator, skin_uniforms, morph_indices, _): &SystemParamItem<
             Self::Param,
         >,
         main_entity: MainEntity,
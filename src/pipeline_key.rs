@@ -2,7 +2,9 @@ use std::ops::BitOr;
 
 use bevy::{
     math::Vec3,
+    mesh::VertexAttributeValues,
     pbr::MeshPipelineKey,
+    platform::collections::HashMap,
     prelude::*,
     render::{
         render_resource::{PrimitiveTopology, TextureFormat},
@@ -11,7 +13,10 @@ use bevy::{
 };
 use bitfield::{bitfield_bitrange, bitfield_fields};
 
-use crate::{uniforms::DepthMode, ComputedOutline, TextureChannel};
+use crate::{
+    uniforms::{outline_mesh_id, DepthMode, DrawMode, OutlineMeshChanged, OutlineMeshData},
+    ComputedOutline, TextureChannel, ATTRIBUTE_OUTLINE_COLOUR, ATTRIBUTE_OUTLINE_WIDTH,
+};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub(crate) enum PassType {
@@ -19,33 +24,44 @@ pub(crate) enum PassType {
     Volume = 2,
     #[cfg(feature = "flood")]
     FloodInit = 3,
+    OccludedVolume = 4,
 }
 
 #[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
-pub(crate) struct RawPipelineKey(u32);
-bitfield_bitrange! {struct RawPipelineKey(u32)}
+pub(crate) struct RawPipelineKey(u64);
+bitfield_bitrange! {struct RawPipelineKey(u64)}
 
 impl RawPipelineKey {
     bitfield_fields! {
         u32;
-        // View parameters (0:7)
+        // View parameters (0:15)
         msaa_samples_minus_one, set_msaa_samples_minus_one: 2, 0;
         pub motion_vector_prepass, set_motion_vector_prepass: 3;
         target_format_code, set_target_format_code: 7, 4;
-        // Mesh parameters (8:15)
-        primitive_topology_int, set_primitive_topology_int: 10, 8;
-        pub morph_targets, set_morph_targets: 11;
-        // Entity parameters (16:29)
-        depth_mode_int, set_depth_mode_int: 17, 16;
-        pub transparent, set_transparent: 18;
-        pub vertex_offset_zero, set_vertex_offset_zero: 19;
-        pub stencil_vertex_offset_zero, set_stencil_vertex_offset_zero: 20;
-        pub plane_offset_zero, set_plane_offset_zero: 21;
-        pub double_sided, set_double_sided: 22;
-        pub alpha_mask_texture, set_alpha_mask_texture: 23;
-        pub alpha_mask_channel_int, set_alpha_mask_channel_int: 25, 24;
-        // Derived parameters (30:31)
-        pass_type_int, set_pass_type_int: 31, 30;
+        pub scene_depth, set_scene_depth: 8;
+        pub scene_depth_multisampled, set_scene_depth_multisampled: 9;
+        pub custom_extrude, set_custom_extrude: 10;
+        // Mesh parameters (16:23)
+        primitive_topology_int, set_primitive_topology_int: 18, 16;
+        pub morph_targets, set_morph_targets: 19;
+        pub mesh_2d, set_mesh_2d: 20;
+        pub vertex_width, set_vertex_width: 21;
+        pub vertex_colour, set_vertex_colour: 22;
+        pub vertex_alpha, set_vertex_alpha: 23;
+        // Entity parameters (24:55)
+        depth_mode_int, set_depth_mode_int: 25, 24;
+        pub transparent, set_transparent: 26;
+        pub vertex_offset_zero, set_vertex_offset_zero: 27;
+        pub stencil_vertex_offset_zero, set_stencil_vertex_offset_zero: 28;
+        pub plane_offset_zero, set_plane_offset_zero: 29;
+        pub double_sided, set_double_sided: 30;
+        pub alpha_mask_texture, set_alpha_mask_texture: 31;
+        pub alpha_mask_channel_int, set_alpha_mask_channel_int: 33, 32;
+        pub occluded_vertex_offset_zero, set_occluded_vertex_offset_zero: 34;
+        pub fill_texture, set_fill_texture: 35;
+        pub material_index, set_material_index: 43, 36;
+        // Derived parameters (56:63)
+        pass_type_int, set_pass_type_int: 58, 56;
     }
 
     pub(crate) fn new() -> Self {
@@ -88,6 +104,7 @@ impl RawPipelineKey {
             x if x == PassType::Volume as u32 => PassType::Volume,
             #[cfg(feature = "flood")]
             x if x == PassType::FloodInit as u32 => PassType::FloodInit,
+            x if x == PassType::OccludedVolume as u32 => PassType::OccludedVolume,
             x => panic!("Invalid value for PassType: {x}"),
         }
     }
@@ -132,6 +149,18 @@ impl ViewPipelineKey {
         self.0.set_motion_vector_prepass(motion_vector_prepass);
         self
     }
+
+    pub(crate) fn with_custom_extrude(mut self, custom_extrude: bool) -> Self {
+        self.0.set_custom_extrude(custom_extrude);
+        self
+    }
+
+    pub(crate) fn with_scene_depth(mut self, scene_depth: Option<Msaa>) -> Self {
+        self.0.set_scene_depth(scene_depth.is_some());
+        self.0
+            .set_scene_depth_multisampled(scene_depth.is_some_and(|msaa| msaa != Msaa::Off));
+        self
+    }
 }
 
 #[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Deref)]
@@ -152,6 +181,26 @@ impl EntityPipelineKey {
         self
     }
 
+    pub(crate) fn with_mesh_2d(mut self, mesh_2d: bool) -> Self {
+        self.0.set_mesh_2d(mesh_2d);
+        self
+    }
+
+    pub(crate) fn with_vertex_width(mut self, vertex_width: bool) -> Self {
+        self.0.set_vertex_width(vertex_width);
+        self
+    }
+
+    pub(crate) fn with_vertex_colour(mut self, vertex_colour: bool) -> Self {
+        self.0.set_vertex_colour(vertex_colour);
+        self
+    }
+
+    pub(crate) fn with_vertex_alpha(mut self, vertex_alpha: bool) -> Self {
+        self.0.set_vertex_alpha(vertex_alpha);
+        self
+    }
+
     pub(crate) fn with_transparent(mut self, transparent: bool) -> Self {
         self.0.set_transparent(transparent);
         self
@@ -181,6 +230,15 @@ impl EntityPipelineKey {
         self
     }
 
+    pub(crate) fn with_occluded_vertex_offset_zero(
+        mut self,
+        occluded_vertex_offset_zero: bool,
+    ) -> Self {
+        self.0
+            .set_occluded_vertex_offset_zero(occluded_vertex_offset_zero);
+        self
+    }
+
     pub(crate) fn with_double_sided(mut self, double_sided: bool) -> Self {
         self.0.set_double_sided(double_sided);
         self
@@ -191,6 +249,16 @@ impl EntityPipelineKey {
         self
     }
 
+    pub(crate) fn with_fill_texture(mut self, fill_texture: bool) -> Self {
+        self.0.set_fill_texture(fill_texture);
+        self
+    }
+
+    pub(crate) fn with_material_index(mut self, material_index: u32) -> Self {
+        self.0.set_material_index(material_index);
+        self
+    }
+
     pub(crate) fn with_alpha_mask_channel(mut self, channel: TextureChannel) -> Self {
         let channel_int = match channel {
             TextureChannel::R => 0,
@@ -212,14 +280,20 @@ impl DerivedPipelineKey {
         entity_key: EntityPipelineKey,
         pass_type: PassType,
     ) -> Self {
+        // Translucent vertex colours only affect the pipeline via transparency
+        let entity_key = entity_key.with_vertex_alpha(false);
         DerivedPipelineKey(
             match pass_type {
                 PassType::Stencil => {
                     view_key.with_motion_vector_prepass(false).0
                         | entity_key
                             .with_transparent(false)
+                            .with_fill_texture(false)
+                            .with_material_index(0)
+                            .with_vertex_colour(false)
                             .with_vertex_offset_zero(entity_key.stencil_vertex_offset_zero())
                             .with_stencil_vertex_offset_zero(false)
+                            .with_occluded_vertex_offset_zero(false)
                             .0
                 }
                 PassType::Volume => {
@@ -228,6 +302,18 @@ impl DerivedPipelineKey {
                             .with_alpha_mask_texture(false)
                             .with_alpha_mask_channel(TextureChannel::A)
                             .with_stencil_vertex_offset_zero(false)
+                            .with_occluded_vertex_offset_zero(false)
+                            .0
+                }
+                PassType::OccludedVolume => {
+                    view_key.0
+                        | entity_key
+                            .with_transparent(true)
+                            .with_alpha_mask_texture(false)
+                            .with_alpha_mask_channel(TextureChannel::A)
+                            .with_vertex_offset_zero(entity_key.occluded_vertex_offset_zero())
+                            .with_stencil_vertex_offset_zero(false)
+                            .with_occluded_vertex_offset_zero(false)
                             .0
                 }
                 #[cfg(feature = "flood")]
@@ -235,11 +321,17 @@ impl DerivedPipelineKey {
                     view_key
                         .with_target_format(TextureFormat::R8Unorm)
                         .with_motion_vector_prepass(false)
+                        .with_custom_extrude(false)
                         .0
                         | entity_key
                             .with_transparent(false)
+                            .with_fill_texture(false)
+                            .with_material_index(0)
                             .with_vertex_offset_zero(true)
+                            .with_vertex_width(false)
+                            .with_vertex_colour(false)
                             .with_stencil_vertex_offset_zero(false)
+                            .with_occluded_vertex_offset_zero(false)
                             .with_plane_offset_zero(true)
                             .0
                 }
@@ -268,33 +360,67 @@ pub(crate) struct ComputedOutlineKey(pub(crate) EntityPipelineKey);
 #[allow(clippy::type_complexity)]
 pub(crate) fn compute_outline_key(
     mut query: Query<
-        (&ComputedOutline, &Mesh3d, &mut ComputedOutlineKey),
-        Or<(
-            Changed<ComputedOutline>,
-            Changed<Mesh3d>,
-            AssetChanged<Mesh3d>,
-        )>,
+        (&ComputedOutline, OutlineMeshData, &mut ComputedOutlineKey),
+        Or<(Changed<ComputedOutline>, OutlineMeshChanged)>,
     >,
     meshes: Res<Assets<Mesh>>,
+    mut mesh_events: MessageReader<AssetEvent<Mesh>>,
+    mut vertex_alpha_cache: Local<HashMap<AssetId<Mesh>, bool>>,
 ) {
-    for (outline, mesh, mut key) in query.iter_mut() {
+    // Scanning the vertex colours is costly, so only do it once per mesh asset
+    for event in mesh_events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            vertex_alpha_cache.remove(id);
+        }
+    }
+    for (outline, mesh_handles, mut key) in query.iter_mut() {
         let Some(outline) = outline.0.as_ref() else {
             continue;
         };
 
-        let Some(mesh) = meshes.get(mesh) else {
+        let Some(mesh_id) = outline_mesh_id(mesh_handles) else {
+            continue;
+        };
+        let Some(mesh) = meshes.get(mesh_id) else {
             continue;
         };
+        let vertex_alpha = *vertex_alpha_cache.entry(mesh_id).or_insert_with(|| {
+            match mesh.attribute(ATTRIBUTE_OUTLINE_COLOUR) {
+                Some(VertexAttributeValues::Float32x4(colours)) => {
+                    colours.iter().any(|colour| colour[3] < 1.0)
+                }
+                _ => false,
+            }
+        });
         key.0 = EntityPipelineKey::new()
             .with_primitive_topology(mesh.primitive_topology())
             .with_morph_targets(mesh.morph_targets().is_some())
-            .with_transparent(!outline.volume.value.colour.is_fully_opaque())
+            .with_mesh_2d(mesh_handles.0.is_none())
+            .with_vertex_width(mesh.contains_attribute(ATTRIBUTE_OUTLINE_WIDTH))
+            .with_vertex_colour(mesh.contains_attribute(ATTRIBUTE_OUTLINE_COLOUR))
+            .with_vertex_alpha(vertex_alpha)
+            .with_transparent(
+                !outline.volume.value.colour.is_fully_opaque()
+                    || outline.distance_scaling.value.fades()
+                    || vertex_alpha,
+            )
             .with_depth_mode(outline.mode.value.depth_mode)
-            .with_vertex_offset_zero(outline.volume.value.offset == 0.0)
+            .with_vertex_offset_zero(
+                outline.volume.value.offset == 0.0
+                    && outline.distance_scaling.value.min_width == 0.0,
+            )
             .with_stencil_vertex_offset_zero(outline.stencil.value.offset == 0.0)
+            .with_occluded_vertex_offset_zero(
+                outline.occluded.value.offset == 0.0
+                    && outline.distance_scaling.value.min_width == 0.0,
+            )
             .with_plane_offset_zero(outline.depth.value.world_plane_offset == Vec3::ZERO)
             .with_double_sided(outline.face.value.double_sided)
             .with_alpha_mask_texture(outline.alpha_mask.value.texture.is_some())
-            .with_alpha_mask_channel(outline.alpha_mask.value.channel);
+            .with_alpha_mask_channel(outline.alpha_mask.value.channel)
+            .with_fill_texture(
+                outline.fill_texture.value.texture.is_some()
+                    && outline.mode.value.draw_mode == DrawMode::Extrude,
+            );
     }
 }
@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use bevy::{
+    anti_alias::{fxaa::fxaa, smaa::smaa},
+    core_pipeline::tonemapping::tonemapping,
+    ecs::{query::QueryItem, schedule::ScheduleLabel},
+    render::{
+        extract_component::ExtractComponent, renderer::ViewQuery, sync_component::SyncComponent,
+    },
+};
+
+use crate::msaa::msaa_extra_writeback_pass;
+use crate::node::outline_render_pass;
+use crate::render_target::outline_composite_pass;
+use crate::OutlinePassPlacement;
+
+pub(crate) const PLACEMENTS: [OutlinePassPlacement; 3] = [
+    OutlinePassPlacement::PreTonemap,
+    OutlinePassPlacement::PostTonemap,
+    OutlinePassPlacement::PostAntiAlias,
+];
+
+impl SyncComponent for OutlinePassPlacement {
+    type Target = OutlinePassPlacement;
+}
+
+impl ExtractComponent for OutlinePassPlacement {
+    type QueryData = &'static OutlinePassPlacement;
+    type QueryFilter = ();
+    type Out = OutlinePassPlacement;
+
+    fn extract_component(placement: QueryItem<'_, '_, Self::QueryData>) -> Option<Self::Out> {
+        Some(*placement)
+    }
+}
+
+/// The outline passes run for views with a given placement, in order.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum OutlinePassSystems {
+    Draw(OutlinePassPlacement),
+    Flood(OutlinePassPlacement),
+    Composite(OutlinePassPlacement),
+}
+
+fn outline_pass_placed(
+    placement: OutlinePassPlacement,
+) -> impl FnMut(ViewQuery<Option<&OutlinePassPlacement>>) -> bool + Clone {
+    move |view: ViewQuery<Option<&OutlinePassPlacement>>| {
+        view.into_inner().copied().unwrap_or_default() == placement
+    }
+}
+
+/// Adds the outline passes to a core pipeline schedule at each placement.
+///
+/// Each view only runs the passes at its own placement.
+pub(crate) fn add_outline_passes(
+    render_app: &mut SubApp,
+    schedule: impl ScheduleLabel + Clone,
+    main_pass: impl SystemSet + Clone,
+    post_process: impl SystemSet + Clone,
+) {
+    for placement in PLACEMENTS {
+        let sets = (
+            OutlinePassSystems::Draw(placement).run_if(outline_pass_placed(placement)),
+            OutlinePassSystems::Flood(placement).run_if(outline_pass_placed(placement)),
+            OutlinePassSystems::Composite(placement).run_if(outline_pass_placed(placement)),
+        )
+            .chain();
+        let sets = match placement {
+            OutlinePassPlacement::PreTonemap => {
+                sets.after(main_pass.clone()).before(post_process.clone())
+            }
+            OutlinePassPlacement::PostTonemap => sets
+                .in_set(post_process.clone())
+                .after(tonemapping)
+                .before(fxaa)
+                .before(smaa),
+            OutlinePassPlacement::PostAntiAlias => {
+                sets.in_set(post_process.clone()).after(fxaa).after(smaa)
+            }
+        };
+
+        render_app
+            .configure_sets(schedule.clone(), sets)
+            .add_systems(
+                schedule.clone(),
+                (
+                    (msaa_extra_writeback_pass, outline_render_pass)
+                        .chain()
+                        .in_set(OutlinePassSystems::Draw(placement)),
+                    outline_composite_pass.in_set(OutlinePassSystems::Composite(placement)),
+                ),
+            );
+    }
+}
@@ -18,17 +18,21 @@ use bevy::render::Extract;
 
 use crate::msaa::ResolvedOutlineMsaa;
 use crate::node::{
-    OpaqueOutline, OutlineBatchSetKey, OutlineBinKey, OutlineSortingInfo, StencilOutline,
-    TransparentOutline,
+    OccludedOutline, OpaqueOutline, OutlineBatchSetKey, OutlineBinKey, OutlineSortingInfo,
+    StencilOutline, TransparentOutline,
 };
+use crate::occlusion::ResolvedSceneDepth;
 use crate::pipeline_key::ComputedOutlineKey;
 use crate::pipeline_key::{DerivedPipelineKey, EntityPipelineKey, PassType, ViewPipelineKey};
-use crate::uniforms::RenderOutlineInstances;
+use crate::render_target::OutlineTargetView;
+use crate::uniforms::{OutlineRingEntities, RenderOutlineInstances};
 use crate::{
-    pipeline::OutlinePipeline, render::DrawOutline, uniforms::DrawMode,
+    pipeline::OutlinePipeline,
+    render::{DrawOutline, DrawOutline2d, DrawOutlineVolume, DrawOutlineVolume2d},
+    uniforms::DrawMode,
     view_uniforms::OutlineQueueStatus,
 };
-use crate::{ComputedOutline, RenderOutlineEntities};
+use crate::{ComputedOutline, OutlineExtrudeShader, RenderOutlineEntities};
 
 #[derive(Clone, Resource, Debug, Default)]
 pub(crate) struct OutlineEntitiesNeedingSpecialisation {
@@ -43,13 +47,18 @@ pub(crate) struct OutlineCache {
 
 #[derive(Default)]
 pub(crate) struct OutlineViewCache {
+    pub(crate) view_key: ViewPipelineKey,
     pub(crate) entity_map: MainEntityHashMap<OutlineCacheEntry>,
+    /// The number of additional rings queued for each entity.
+    pub(crate) queued_rings: MainEntityHashMap<usize>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct OutlineCacheEntry {
     pub(crate) stencil_pipeline_id: CachedRenderPipelineId,
     pub(crate) volume_pipeline_id: CachedRenderPipelineId,
+    pub(crate) occluded_pipeline_id: CachedRenderPipelineId,
+    pub(crate) ring_pipeline_ids: [CachedRenderPipelineId; 2],
 }
 
 #[derive(Default, Deref, DerefMut, Resource)]
@@ -117,34 +126,46 @@ pub(crate) fn specialise_outlines(
     render_outlines: Res<RenderOutlineInstances>,
     render_visible: Res<RenderOutlineEntities>,
     mut pending_queues: ResMut<PendingOutlineQueues>,
-    specialisations: Res<DirtyOutlineSpecialisations>,
+    mut specialisations: ResMut<DirtyOutlineSpecialisations>,
     mut outline_cache: ResMut<OutlineCache>,
     mut pipelines: ResMut<SpecializedMeshPipelines<OutlinePipeline>>,
     mut all_views: Local<HashSet<RetainedViewEntity>>,
     mut warm_up_keys: Local<Vec<EntityPipelineKey>>,
     outline_pipeline: Res<OutlinePipeline>,
     pipeline_cache: Res<PipelineCache>,
+    extrude_shader: Option<Res<OutlineExtrudeShader>>,
     views: Query<(
         &ExtractedView,
         Has<MotionVectorPrepass>,
         &ResolvedOutlineMsaa,
+        Option<&ResolvedSceneDepth>,
+        Option<&OutlineTargetView>,
     )>,
 ) {
     all_views.clear();
 
-    for (view, motion_vector_prepass, msaa) in &views {
+    for (view, motion_vector_prepass, msaa, scene_depth, target_view) in &views {
         all_views.insert(view.retained_view_entity);
 
         let view_key = ViewPipelineKey::new()
             .with_msaa(**msaa)
-            .with_target_format(view.target_format)
-            .with_motion_vector_prepass(motion_vector_prepass);
+            .with_target_format(target_view.map_or(view.target_format, |t| t.format))
+            .with_motion_vector_prepass(motion_vector_prepass)
+            .with_custom_extrude(extrude_shader.is_some())
+            .with_scene_depth(scene_depth.map(|scene_depth| **scene_depth));
 
         let outline_view_cache = outline_cache
             .view_map
             .entry(view.retained_view_entity)
             .or_default();
 
+        // Respecialise everything if the view key changes, e.g. when scene
+        // occlusion is toggled, as this changes the view bind group layout
+        if outline_view_cache.view_key != view_key {
+            outline_view_cache.view_key = view_key;
+            specialisations.views.insert(view.retained_view_entity);
+        }
+
         if specialisations.must_wipe_specializations_for_view(view.retained_view_entity) {
             outline_view_cache.entity_map.clear();
         } else {
@@ -248,12 +269,71 @@ pub(crate) fn specialise_outlines(
                     CachedRenderPipelineId::INVALID
                 };
 
+                // Specialise occluded volume pipeline
+                let occluded_pipeline_id = if outline.occluded
+                    && outline.draw_mode == DrawMode::Extrude
+                    && view_key.scene_depth()
+                {
+                    let occluded_key =
+                        DerivedPipelineKey::new(view_key, *warm_up_key, PassType::OccludedVolume);
+
+                    match pipelines.specialize(
+                        &pipeline_cache,
+                        &outline_pipeline,
+                        occluded_key,
+                        &mesh.layout,
+                    ) {
+                        Ok(pipeline_id) => pipeline_id,
+                        Err(err) => {
+                            error!("Failed to specialise occluded volume pipeline: {}", err);
+                            CachedRenderPipelineId::INVALID
+                        }
+                    }
+                } else {
+                    CachedRenderPipelineId::INVALID
+                };
+
+                // Specialise ring pipelines, indexed by transparency
+                let mut ring_pipeline_ids = [CachedRenderPipelineId::INVALID; 2];
+                if outline.draw_mode == DrawMode::Extrude {
+                    for ring in outline.rings.iter() {
+                        let transparent = outline.is_transparent(ring.colour);
+                        if ring_pipeline_ids[transparent as usize]
+                            != CachedRenderPipelineId::INVALID
+                        {
+                            continue;
+                        }
+                        let ring_key = DerivedPipelineKey::new(
+                            view_key,
+                            warm_up_key
+                                .with_transparent(transparent)
+                                .with_vertex_offset_zero(false),
+                            PassType::Volume,
+                        );
+
+                        ring_pipeline_ids[transparent as usize] = match pipelines.specialize(
+                            &pipeline_cache,
+                            &outline_pipeline,
+                            ring_key,
+                            &mesh.layout,
+                        ) {
+                            Ok(pipeline_id) => pipeline_id,
+                            Err(err) => {
+                                error!("Failed to specialise ring pipeline: {}", err);
+                                continue;
+                            }
+                        };
+                    }
+                }
+
                 if first_key {
                     outline_view_cache.entity_map.insert(
                         *main_entity,
                         OutlineCacheEntry {
                             stencil_pipeline_id,
                             volume_pipeline_id,
+                            occluded_pipeline_id,
+                            ring_pipeline_ids,
                         },
                     );
                     first_key = false;
@@ -273,41 +353,66 @@ pub(crate) fn queue_outline_mesh(
     stencil_draw_functions: Res<DrawFunctions<StencilOutline>>,
     opaque_draw_functions: Res<DrawFunctions<OpaqueOutline>>,
     transparent_draw_functions: Res<DrawFunctions<TransparentOutline>>,
+    occluded_draw_functions: Res<DrawFunctions<OccludedOutline>>,
     mesh_allocator: Res<MeshAllocator>,
-    outline_cache: Res<OutlineCache>,
+    mut outline_cache: ResMut<OutlineCache>,
     render_outlines: Res<RenderOutlineInstances>,
+    ring_entities: Res<OutlineRingEntities>,
     render_visible: Res<RenderOutlineEntities>,
     pending_queues: Res<PendingOutlineQueues>,
     specialisations: Res<DirtyOutlineSpecialisations>,
     mut stencil_phases: ResMut<ViewBinnedRenderPhases<StencilOutline>>,
     mut opaque_phases: ResMut<ViewBinnedRenderPhases<OpaqueOutline>>,
     mut transparent_phases: ResMut<ViewSortedRenderPhases<TransparentOutline>>,
+    mut occluded_phases: ResMut<ViewSortedRenderPhases<OccludedOutline>>,
     mut views: Query<(&ExtractedView, &mut OutlineQueueStatus)>,
 ) {
-    let draw_stencil = stencil_draw_functions
-        .read()
-        .get_id::<DrawOutline>()
-        .unwrap();
-    let draw_opaque_outline = opaque_draw_functions
-        .read()
-        .get_id::<DrawOutline>()
-        .unwrap();
-    let draw_transparent_outline = transparent_draw_functions
-        .read()
-        .get_id::<DrawOutline>()
-        .unwrap();
+    // Draw functions indexed by whether the outline belongs to a 2D mesh
+    let draw_stencil = [
+        stencil_draw_functions.read().get_id::<DrawOutline>(),
+        stencil_draw_functions.read().get_id::<DrawOutline2d>(),
+    ]
+    .map(Option::unwrap);
+    let draw_opaque_outline = [
+        opaque_draw_functions.read().get_id::<DrawOutlineVolume>(),
+        opaque_draw_functions.read().get_id::<DrawOutlineVolume2d>(),
+    ]
+    .map(Option::unwrap);
+    let draw_transparent_outline = [
+        transparent_draw_functions
+            .read()
+            .get_id::<DrawOutlineVolume>(),
+        transparent_draw_functions
+            .read()
+            .get_id::<DrawOutlineVolume2d>(),
+    ]
+    .map(Option::unwrap);
+    let draw_occluded_outline = [
+        occluded_draw_functions.read().get_id::<DrawOutlineVolume>(),
+        occluded_draw_functions
+            .read()
+            .get_id::<DrawOutlineVolume2d>(),
+    ]
+    .map(Option::unwrap);
 
     for (view, mut queue_status) in views.iter_mut() {
         let outline_view_cache = outline_cache
             .view_map
-            .get(&view.retained_view_entity)
+            .get_mut(&view.retained_view_entity)
             .unwrap();
 
-        let (Some(stencil_phase), Some(opaque_phase), Some(transparent_phase)) = (
+        let (
+            Some(stencil_phase),
+            Some(opaque_phase),
+            Some(transparent_phase),
+            Some(occluded_phase),
+        ) = (
             stencil_phases.get_mut(&view.retained_view_entity),
             opaque_phases.get_mut(&view.retained_view_entity),
             transparent_phases.get_mut(&view.retained_view_entity),
-        ) else {
+            occluded_phases.get_mut(&view.retained_view_entity),
+        )
+        else {
             continue; // No render phase
         };
 
@@ -325,6 +430,12 @@ pub(crate) fn queue_outline_mesh(
             stencil_phase.remove(main_entity);
             opaque_phase.remove(main_entity);
             transparent_phase.remove(Entity::PLACEHOLDER, main_entity);
+            occluded_phase.remove(Entity::PLACEHOLDER, main_entity);
+            if let Some(ring_count) = outline_view_cache.queued_rings.remove(&main_entity) {
+                for index in 0..ring_count {
+                    transparent_phase.remove(ring_entities.get(index), main_entity);
+                }
+            }
         }
 
         for (_, main_entity) in specialisations.iter_to_queue(
@@ -336,6 +447,7 @@ pub(crate) fn queue_outline_mesh(
                 continue;
             };
 
+            let mesh_2d = outline.pipeline_key.mesh_2d() as usize;
             let mesh_slabs = mesh_allocator.mesh_slabs(&outline.mesh_id);
             let vertex_slab = mesh_slabs.map(|s| s.vertex_slab_id);
             let index_slab = mesh_slabs.and_then(|s| s.index_slab_id);
@@ -348,6 +460,8 @@ pub(crate) fn queue_outline_mesh(
             let Some(OutlineCacheEntry {
                 stencil_pipeline_id,
                 volume_pipeline_id,
+                occluded_pipeline_id,
+                ring_pipeline_ids,
             }) = outline_view_cache.entity_map.get(main_entity)
             else {
                 continue;
@@ -358,13 +472,14 @@ pub(crate) fn queue_outline_mesh(
                 stencil_phase.add(
                     OutlineBatchSetKey {
                         pipeline: *stencil_pipeline_id,
-                        draw_function: draw_stencil,
+                        draw_function: draw_stencil[mesh_2d],
                         vertex_slab: vertex_slab.unwrap_or_default(),
                         index_slab,
                     },
                     OutlineBinKey {
                         asset_id: outline.mesh_id,
                         texture_id: outline.alpha_mask_id,
+                        material_id: None,
                     },
                     (Entity::PLACEHOLDER, *main_entity),
                     InputUniformIndex::default(),
@@ -374,7 +489,7 @@ pub(crate) fn queue_outline_mesh(
 
             // Queue volume pass if needed
             if outline.volume && outline.draw_mode == DrawMode::Extrude {
-                let transparent = outline.instance_data.volume_colour[3] < 1.0;
+                let transparent = outline.is_transparent(outline.instance_data.volume_colour);
 
                 if transparent {
                     let sorting_info = OutlineSortingInfo {
@@ -386,7 +501,7 @@ pub(crate) fn queue_outline_mesh(
                         entity: Entity::PLACEHOLDER,
                         main_entity: *main_entity,
                         pipeline: *volume_pipeline_id,
-                        draw_function: draw_transparent_outline,
+                        draw_function: draw_transparent_outline[mesh_2d],
                         distance: 0.0,
                         batch_range: 0..0,
                         extra_index: PhaseItemExtraIndex::None,
@@ -396,13 +511,14 @@ pub(crate) fn queue_outline_mesh(
                     opaque_phase.add(
                         OutlineBatchSetKey {
                             pipeline: *volume_pipeline_id,
-                            draw_function: draw_opaque_outline,
+                            draw_function: draw_opaque_outline[mesh_2d],
                             vertex_slab: vertex_slab.unwrap_or_default(),
                             index_slab,
                         },
                         OutlineBinKey {
                             asset_id: outline.mesh_id,
-                            texture_id: outline.alpha_mask_id,
+                            texture_id: outline.fill_texture_id,
+                            material_id: outline.material_id,
                         },
                         (Entity::PLACEHOLDER, *main_entity),
                         InputUniformIndex::default(),
@@ -410,9 +526,60 @@ pub(crate) fn queue_outline_mesh(
                     );
                 }
             }
+
+            // Queue additional rings after the volume, so that each ring is
+            // drawn outside of those before it
+            if outline.volume && outline.draw_mode == DrawMode::Extrude {
+                for (index, ring) in outline.rings.iter().enumerate() {
+                    let pipeline = ring_pipeline_ids[outline.is_transparent(ring.colour) as usize];
+                    if pipeline == CachedRenderPipelineId::INVALID {
+                        continue;
+                    }
+                    let sorting_info = OutlineSortingInfo {
+                        world_plane_origin: outline.instance_data.world_plane_origin,
+                        world_plane_offset: outline.instance_data.world_plane_offset,
+                    };
+                    transparent_phase.add_retained(TransparentOutline {
+                        sorting_info,
+                        entity: ring_entities.get(index),
+                        main_entity: *main_entity,
+                        pipeline,
+                        draw_function: draw_transparent_outline[mesh_2d],
+                        distance: 0.0,
+                        batch_range: 0..0,
+                        extra_index: PhaseItemExtraIndex::None,
+                        indexed: index_slab.is_some(),
+                    });
+                }
+                outline_view_cache
+                    .queued_rings
+                    .insert(*main_entity, outline.rings.len());
+            }
+
+            // Queue occluded volume pass if needed
+            if *occluded_pipeline_id != CachedRenderPipelineId::INVALID {
+                let sorting_info = OutlineSortingInfo {
+                    world_plane_origin: outline.instance_data.world_plane_origin,
+                    world_plane_offset: outline.instance_data.world_plane_offset,
+                };
+                occluded_phase.add_retained(OccludedOutline {
+                    sorting_info,
+                    entity: Entity::PLACEHOLDER,
+                    main_entity: *main_entity,
+                    pipeline: *occluded_pipeline_id,
+                    draw_function: draw_occluded_outline[mesh_2d],
+                    distance: 0.0,
+                    batch_range: 0..0,
+                    extra_index: PhaseItemExtraIndex::None,
+                    indexed: index_slab.is_some(),
+                });
+            }
         }
 
-        if !opaque_phase.is_empty() || !transparent_phase.items.is_empty() {
+        if !opaque_phase.is_empty()
+            || !transparent_phase.items.is_empty()
+            || !occluded_phase.items.is_empty()
+        {
             queue_status.has_volume = true;
         }
     }
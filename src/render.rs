@@ -9,6 +9,8 @@ use bevy::{
     pbr::{DrawMesh, SetMeshBindGroup},
     render::{
         extract_component::DynamicUniformIndex,
+        mesh::{allocator::MeshAllocator, RenderMesh, RenderMeshBufferInfo},
+        render_asset::RenderAssets,
         render_phase::{
             PhaseItem, PhaseItemExtraIndex, RenderCommand, RenderCommandResult, SetItemPipeline,
             TrackedRenderPass,
@@ -17,8 +19,12 @@ use bevy::{
 };
 
 use crate::{
-    uniforms::{AlphaMaskBindGroups, OutlineInstanceBindGroup, RenderOutlineInstances},
-    view_uniforms::{OutlineViewBindGroup, OutlineViewUniform},
+    material::OutlineMaterialBindGroups,
+    uniforms::{
+        AlphaMaskBindGroups, FillTextureBindGroups, OutlineInstanceBindGroup,
+        OutlineMesh2dBindGroup, RenderOutlineInstances,
+    },
+    view_uniforms::{OutlineViewBindGroup, OutlineViewSceneDepthBindGroup, OutlineViewUniform},
 };
 
 pub(crate) struct SetOutlineInstanceBindGroup<const I: usize>();
@@ -50,17 +56,24 @@ impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetOutlineInstanceBindGr
 pub(crate) struct SetOutlineViewBindGroup<const I: usize>();
 
 impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetOutlineViewBindGroup<I> {
-    type ViewQuery = Read<DynamicUniformIndex<OutlineViewUniform>>;
+    type ViewQuery = (
+        Read<DynamicUniformIndex<OutlineViewUniform>>,
+        Option<Read<OutlineViewSceneDepthBindGroup>>,
+    );
     type ItemQuery = ();
     type Param = SRes<OutlineViewBindGroup>;
     fn render<'w>(
         _item: &P,
-        view_data: ROQueryItem<'w, '_, Self::ViewQuery>,
+        (view_index, scene_depth_bind_group): ROQueryItem<'w, '_, Self::ViewQuery>,
         _entity_data: Option<()>,
         bind_group: SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        pass.set_bind_group(I, &bind_group.into_inner().bind_group, &[view_data.index()]);
+        let bind_group = match scene_depth_bind_group {
+            Some(scene_depth_bind_group) => &scene_depth_bind_group.bind_group,
+            None => &bind_group.into_inner().bind_group,
+        };
+        pass.set_bind_group(I, bind_group, &[view_index.index()]);
         RenderCommandResult::Success
     }
 }
@@ -96,6 +109,130 @@ impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetOutlineAlphaMaskBindG
     }
 }
 
+/// Sets the bind group of the outline's material, or else of its fill texture.
+pub(crate) struct SetOutlineFillTextureBindGroup<const I: usize>();
+
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetOutlineFillTextureBindGroup<I> {
+    type ViewQuery = ();
+    type ItemQuery = ();
+    type Param = (
+        SRes<FillTextureBindGroups>,
+        SRes<AlphaMaskBindGroups>,
+        SRes<OutlineMaterialBindGroups>,
+        SRes<RenderOutlineInstances>,
+    );
+    fn render<'w>(
+        item: &P,
+        _view_data: (),
+        _entity_data: Option<ROQueryItem<'w, '_, Self::ItemQuery>>,
+        param: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let (fill_bind_groups, alpha_mask_bind_groups, material_bind_groups, render_outlines) =
+            param;
+        let Some(outline) = render_outlines.get(&item.main_entity()) else {
+            return RenderCommandResult::Failure("No outline found for entity.");
+        };
+        // Volume pipelines without a fill texture use the alpha mask layout
+        let bind_group = if outline.pipeline_key.material_index() != 0 {
+            let Some(bind_group) = outline
+                .material_id
+                .and_then(|material_id| material_bind_groups.into_inner().get(&material_id))
+            else {
+                return RenderCommandResult::Skip; // Material not yet prepared
+            };
+            bind_group
+        } else if outline.pipeline_key.fill_texture() {
+            let fill_bind_groups = fill_bind_groups.into_inner();
+            outline
+                .fill_texture_id
+                .and_then(|texture_id| fill_bind_groups.bind_groups.get(&texture_id))
+                .unwrap_or(&fill_bind_groups.default_bind_group)
+        } else {
+            &alpha_mask_bind_groups.into_inner().default_bind_group
+        };
+
+        pass.set_bind_group(I, bind_group, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+pub(crate) struct SetOutlineMesh2dBindGroup<const I: usize>();
+
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetOutlineMesh2dBindGroup<I> {
+    type ViewQuery = ();
+    type ItemQuery = ();
+    type Param = SRes<OutlineMesh2dBindGroup>;
+    fn render<'w>(
+        _item: &P,
+        _view_data: (),
+        _entity_data: Option<()>,
+        bind_group: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        pass.set_bind_group(I, &bind_group.into_inner().bind_group, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+/// Draws the mesh recorded in the extracted outline, as 2D meshes are absent
+/// from the render mesh instances used by [`DrawMesh`].
+pub(crate) struct DrawOutlineMesh2d;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawOutlineMesh2d {
+    type ViewQuery = ();
+    type ItemQuery = ();
+    type Param = (
+        SRes<RenderAssets<RenderMesh>>,
+        SRes<RenderOutlineInstances>,
+        SRes<MeshAllocator>,
+    );
+    fn render<'w>(
+        item: &P,
+        _view_data: (),
+        _entity_data: Option<()>,
+        (meshes, render_outlines, mesh_allocator): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let meshes = meshes.into_inner();
+        let mesh_allocator = mesh_allocator.into_inner();
+        let Some(outline) = render_outlines.get(&item.main_entity()) else {
+            return RenderCommandResult::Failure("No outline found for entity.");
+        };
+        let Some(gpu_mesh) = meshes.get(outline.mesh_id) else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(vertex_buffer_slice) = mesh_allocator.mesh_vertex_slice(&outline.mesh_id) else {
+            return RenderCommandResult::Skip;
+        };
+
+        pass.set_vertex_buffer(0, vertex_buffer_slice.buffer.slice(..));
+
+        let batch_range = item.batch_range();
+        match &gpu_mesh.buffer_info {
+            RenderMeshBufferInfo::Indexed {
+                index_format,
+                count,
+            } => {
+                let Some(index_buffer_slice) = mesh_allocator.mesh_index_slice(&outline.mesh_id)
+                else {
+                    return RenderCommandResult::Skip;
+                };
+                pass.set_index_buffer(index_buffer_slice.buffer.slice(..), *index_format);
+                pass.draw_indexed(
+                    index_buffer_slice.range.start..(index_buffer_slice.range.start + count),
+                    vertex_buffer_slice.range.start as i32,
+                    batch_range.clone(),
+                );
+            }
+            RenderMeshBufferInfo::NonIndexed => {
+                pass.draw(vertex_buffer_slice.range, batch_range.clone());
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
+
 pub(crate) type DrawOutline = (
     SetItemPipeline,
     SetOutlineViewBindGroup<0>,
@@ -104,3 +241,30 @@ pub(crate) type DrawOutline = (
     SetOutlineAlphaMaskBindGroup<3>,
     DrawMesh,
 );
+
+pub(crate) type DrawOutlineVolume = (
+    SetItemPipeline,
+    SetOutlineViewBindGroup<0>,
+    SetOutlineInstanceBindGroup<1>,
+    SetMeshBindGroup<2>,
+    SetOutlineFillTextureBindGroup<3>,
+    DrawMesh,
+);
+
+pub(crate) type DrawOutline2d = (
+    SetItemPipeline,
+    SetOutlineViewBindGroup<0>,
+    SetOutlineInstanceBindGroup<1>,
+    SetOutlineMesh2dBindGroup<2>,
+    SetOutlineAlphaMaskBindGroup<3>,
+    DrawOutlineMesh2d,
+);
+
+pub(crate) type DrawOutlineVolume2d = (
+    SetItemPipeline,
+    SetOutlineViewBindGroup<0>,
+    SetOutlineInstanceBindGroup<1>,
+    SetOutlineMesh2dBindGroup<2>,
+    SetOutlineFillTextureBindGroup<3>,
+    DrawOutlineMesh2d,
+);
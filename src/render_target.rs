@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use bevy::{
+    core_pipeline::blit::{BlitPipeline, BlitPipelineKey},
+    ecs::query::QueryItem,
+    render::{
+        camera::ExtractedCamera,
+        extract_component::ExtractComponent,
+        render_asset::RenderAssets,
+        render_resource::{
+            BlendState, CachedRenderPipelineId, PipelineCache, RenderPassDescriptor,
+            SpecializedRenderPipelines, TextureFormat, TextureView,
+        },
+        renderer::{RenderContext, ViewQuery},
+        sync_component::SyncComponent,
+        texture::GpuImage,
+        view::{Msaa, ViewTarget},
+    },
+};
+
+use crate::OutlineRenderTarget;
+
+impl SyncComponent for OutlineRenderTarget {
+    type Target = OutlineRenderTarget;
+}
+
+impl ExtractComponent for OutlineRenderTarget {
+    type QueryData = &'static OutlineRenderTarget;
+    type QueryFilter = ();
+    type Out = OutlineRenderTarget;
+
+    fn extract_component(render_target: QueryItem<'_, '_, Self::QueryData>) -> Option<Self::Out> {
+        Some(render_target.clone())
+    }
+}
+
+/// The image which outlines are drawn into for views with an
+/// [`OutlineRenderTarget`].
+#[derive(Component)]
+pub(crate) struct OutlineTargetView {
+    pub(crate) view: TextureView,
+    pub(crate) format: TextureFormat,
+}
+
+pub(crate) fn prepare_outline_target_views(
+    mut commands: Commands,
+    views: Query<(Entity, &ExtractedCamera, Option<&OutlineRenderTarget>)>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+) {
+    for (entity, camera, render_target) in views.iter() {
+        let Some(gpu_image) = render_target.and_then(|t| gpu_images.get(&t.image)) else {
+            commands.entity(entity).remove::<OutlineTargetView>();
+            continue;
+        };
+        if camera.physical_target_size != Some(gpu_image.size_2d()) {
+            warn_once!(
+                "The image of an OutlineRenderTarget must be the same size as the camera's target"
+            );
+            commands.entity(entity).remove::<OutlineTargetView>();
+            continue;
+        }
+        commands.entity(entity).insert(OutlineTargetView {
+            view: gpu_image.texture_view.clone(),
+            format: gpu_image.texture_format,
+        });
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct OutlineCompositePipeline(CachedRenderPipelineId);
+
+pub(crate) fn prepare_outline_composite_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<BlitPipeline>>,
+    blit_pipeline: Res<BlitPipeline>,
+    views: Query<(Entity, &ViewTarget, &Msaa, Option<&OutlineRenderTarget>)>,
+) {
+    for (entity, view_target, msaa, render_target) in views.iter() {
+        if render_target.is_some_and(|t| t.composite) {
+            // Outlines are blended over a transparent image, leaving its
+            // colour premultiplied by alpha.
+            let key = BlitPipelineKey {
+                target_format: view_target.main_texture_format(),
+                samples: msaa.samples(),
+                blend_state: Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                source_space: None,
+            };
+
+            let pipeline = pipelines.specialize(&pipeline_cache, &blit_pipeline, key);
+            commands
+                .entity(entity)
+                .insert(OutlineCompositePipeline(pipeline));
+        } else {
+            commands.entity(entity).remove::<OutlineCompositePipeline>();
+        }
+    }
+}
+
+/// Composites the outlines drawn into an [`OutlineRenderTarget`] back onto
+/// the view target.
+pub(crate) fn outline_composite_pass(
+    view: ViewQuery<(&ViewTarget, &OutlineTargetView, &OutlineCompositePipeline)>,
+    blit_pipeline: Res<BlitPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    mut render_context: RenderContext,
+) {
+    let (target, target_view, pipeline_id) = view.into_inner();
+
+    let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id.0) else {
+        return;
+    };
+
+    let bind_group = blit_pipeline.create_bind_group(
+        render_context.render_device(),
+        &target_view.view,
+        &pipeline_cache,
+    );
+
+    let pass_descriptor = RenderPassDescriptor {
+        label: Some("outline_composite_pass"),
+        color_attachments: &[Some(target.get_color_attachment())],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+        multiview_mask: None,
+    };
+
+    let mut render_pass = render_context
+        .command_encoder()
+        .begin_render_pass(&pass_descriptor);
+
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+}
@@ -0,0 +1,33 @@
+#define_import_path bevy_mod_outline::scene_depth
+
+#import bevy_mod_outline::common::VertexOutput
+
+#ifdef SCENE_DEPTH
+#ifdef SCENE_DEPTH_MULTISAMPLED
+@group(0) @binding(1) var scene_depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(1) var scene_depth_texture: texture_depth_2d;
+#endif
+#endif
+
+// Returns whether a fragment passes the scene depth test of its pass. Outside
+// of the occluded pass, fragments behind the scene fail. In the occluded pass,
+// only those fragments pass. All fragments pass without scene occlusion.
+fn outline_scene_depth_test(vertex: VertexOutput) -> bool {
+#ifdef SCENE_DEPTH
+#ifdef FLAT_DEPTH
+    let depth = vertex.flat_depth;
+#else
+    let depth = vertex.position.z;
+#endif
+    // Reverse-Z, so the scene is in front where its depth is greater.
+    let scene_depth = textureLoad(scene_depth_texture, vec2<i32>(vertex.position.xy), 0);
+#ifdef OCCLUDED
+    return depth < scene_depth;
+#else
+    return depth >= scene_depth;
+#endif
+#else
+    return true;
+#endif
+}
@@ -0,0 +1,151 @@
+use bevy::image::{TextureAtlas, TextureAtlasLayout};
+use bevy::prelude::*;
+#[cfg(feature = "sprite")]
+use bevy::sprite::{Anchor, Sprite};
+
+#[cfg(feature = "sprite")]
+use crate::{computed::Source, uniforms::DrawMode, ComputedOutline};
+
+/// The alpha threshold used to derive the silhouette of a sprite or image
+/// node, unless an [`OutlineAlphaMask`](crate::OutlineAlphaMask) is specified.
+pub(crate) const SPRITE_ALPHA_THRESHOLD: f32 = 0.5;
+
+/// A component for storing the quad drawn in place of a mesh when outlining
+/// a sprite or UI node.
+#[derive(Clone, Component, PartialEq)]
+pub(crate) struct ComputedSpriteOutline {
+    pub(crate) mesh_id: AssetId<Mesh>,
+    pub(crate) local_from_quad: Affine3A,
+    /// The world transform of a UI node, which has no `GlobalTransform` of
+    /// its own. Sprites use the transform of their entity.
+    pub(crate) world_from_local: Option<Affine3A>,
+    /// UV offset in `xy` and scale in `zw` selecting the sprite's region of
+    /// its image.
+    pub(crate) uv_rect: Vec4,
+    /// The only camera which the quad is visible to, being the target camera
+    /// of a UI node.
+    pub(crate) camera: Option<Entity>,
+}
+
+/// The unit quad shared by all outlined sprites and UI nodes.
+#[derive(Resource)]
+pub(crate) struct SpriteOutlineQuad(pub(crate) Handle<Mesh>);
+
+impl FromWorld for SpriteOutlineQuad {
+    fn from_world(world: &mut World) -> Self {
+        SpriteOutlineQuad(
+            world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Rectangle::new(1.0, 1.0)),
+        )
+    }
+}
+
+/// Derives the outline quad of each outlined sprite, and substitutes the
+/// sprite's image as the alpha mask for outlines which do not specify one.
+///
+/// Sprites are always outlined using jump flood, as their quads have no
+/// geometry to extrude.
+#[cfg(feature = "sprite")]
+#[allow(clippy::type_complexity)]
+pub(crate) fn compute_sprite_outline(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        Ref<Sprite>,
+        &Anchor,
+        &mut ComputedOutline,
+        Option<&mut ComputedSpriteOutline>,
+    )>,
+    quad: Res<SpriteOutlineQuad>,
+    images: Res<Assets<Image>>,
+    atlas_layouts: Res<Assets<TextureAtlasLayout>>,
+) {
+    for (entity, sprite, anchor, mut computed, sprite_outline) in query.iter_mut() {
+        if sprite.is_changed() || computed.is_changed() {
+            if let ComputedOutline(Some(computed)) = computed.as_mut() {
+                computed.mode.value.draw_mode = DrawMode::JumpFlood;
+                if computed.alpha_mask.value.texture.is_none() {
+                    computed.alpha_mask.value.texture = Some(sprite.image.clone());
+                    if computed.alpha_mask.source == Source::Default {
+                        computed.alpha_mask.value.threshold = SPRITE_ALPHA_THRESHOLD;
+                    }
+                }
+            }
+        }
+
+        let image_size = images.get(&sprite.image).map(|image| image.size_f32());
+        let rect = image_region(sprite.texture_atlas.as_ref(), sprite.rect, &atlas_layouts);
+        let Some(size) = sprite
+            .custom_size
+            .or(rect.map(|rect| rect.size()))
+            .or(image_size)
+        else {
+            continue; // Image not yet loaded
+        };
+        let uv_rect = region_uv_rect(rect, image_size, sprite.flip_x, sprite.flip_y);
+
+        let new_sprite_outline = ComputedSpriteOutline {
+            mesh_id: quad.0.id(),
+            local_from_quad: Affine3A::from_scale_rotation_translation(
+                size.extend(1.0),
+                Quat::IDENTITY,
+                (-anchor.as_vec() * size).extend(0.0),
+            ),
+            world_from_local: None,
+            uv_rect,
+            camera: None,
+        };
+        match sprite_outline {
+            Some(mut sprite_outline) => {
+                sprite_outline.set_if_neq(new_sprite_outline);
+            }
+            None => {
+                commands.entity(entity).insert(new_sprite_outline);
+            }
+        }
+    }
+}
+
+/// Returns the region of an image selected by a texture atlas and a rect
+/// within it, or `None` if the whole image is used.
+pub(crate) fn image_region(
+    atlas: Option<&TextureAtlas>,
+    rect: Option<Rect>,
+    atlas_layouts: &Assets<TextureAtlasLayout>,
+) -> Option<Rect> {
+    let atlas_rect = atlas
+        .and_then(|atlas| atlas.texture_rect(atlas_layouts))
+        .map(|rect| rect.as_rect());
+    match (atlas_rect, rect) {
+        (None, rect) => rect,
+        (Some(atlas_rect), None) => Some(atlas_rect),
+        (Some(atlas_rect), Some(rect)) => Some(Rect {
+            min: rect.min + atlas_rect.min,
+            max: rect.max + atlas_rect.min,
+        }),
+    }
+}
+
+/// Returns the UV offset and scale selecting a region of an image, flipped
+/// as specified.
+pub(crate) fn region_uv_rect(
+    rect: Option<Rect>,
+    image_size: Option<Vec2>,
+    flip_x: bool,
+    flip_y: bool,
+) -> Vec4 {
+    let mut uv_rect = match (rect, image_size) {
+        (Some(rect), Some(image_size)) => (rect.min / image_size, rect.size() / image_size).into(),
+        _ => Vec4::new(0.0, 0.0, 1.0, 1.0),
+    };
+    if flip_x {
+        uv_rect.x += uv_rect.z;
+        uv_rect.z = -uv_rect.z;
+    }
+    if flip_y {
+        uv_rect.y += uv_rect.w;
+        uv_rect.w = -uv_rect.w;
+    }
+    uv_rect
+}
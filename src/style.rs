@@ -0,0 +1,349 @@
+use bevy::asset::AsAssetId;
+#[cfg(feature = "style_loader")]
+use bevy::asset::{io::Reader, AssetLoader, LoadContext};
+use bevy::ecs::{lifecycle::HookContext, world::DeferredWorld};
+use bevy::prelude::*;
+
+use crate::computed::StyledOutline;
+#[cfg(feature = "style_loader")]
+use crate::TextureChannel;
+use crate::{
+    OutlineAlphaMask, OutlineFace, OutlineMode, OutlinePlaneDepth, OutlineStencil, OutlineVolume,
+};
+
+/// An asset describing the look of an outline.
+///
+/// Each property which is set is applied to entities with an
+/// [`OutlineStyleHandle`] referring to this style, as though the
+/// corresponding component was added to the entity. Components added
+/// directly take priority over the style, while the style takes priority over
+/// any properties inherited from a parent.
+///
+/// With the `style_loader` feature enabled, styles may be loaded from RON
+/// files with the extension `.outline.ron` or JSON files with the extension
+/// `.outline.json`. The alpha mask texture is given as an asset path. Changes
+/// to a style, such as when its file is hot-reloaded, apply to all entities
+/// using it.
+#[derive(Asset, Clone, Default, TypePath)]
+pub struct OutlineStyle {
+    /// The outline volume, as per [`OutlineVolume`].
+    pub volume: Option<OutlineVolume>,
+    /// The outline stencil, as per [`OutlineStencil`].
+    pub stencil: Option<OutlineStencil>,
+    /// The outline mode, as per [`OutlineMode`].
+    pub mode: Option<OutlineMode>,
+    /// The faces rendered, as per [`OutlineFace`].
+    pub face: Option<OutlineFace>,
+    /// The outline plane depth, as per [`OutlinePlaneDepth`].
+    pub plane_depth: Option<OutlinePlaneDepth>,
+    /// The alpha mask, as per [`OutlineAlphaMask`].
+    pub alpha_mask: Option<OutlineAlphaMask>,
+}
+
+/// A component for applying an [`OutlineStyle`] to an entity.
+#[derive(Clone, Component, Debug, Default, Deref, DerefMut, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+#[component(on_remove = remove_styled_outline)]
+pub struct OutlineStyleHandle(pub Handle<OutlineStyle>);
+
+impl AsAssetId for OutlineStyleHandle {
+    type Asset = OutlineStyle;
+
+    fn as_asset_id(&self) -> AssetId<Self::Asset> {
+        self.id()
+    }
+}
+
+/// Removes the properties of the style when `OutlineStyleHandle` is removed.
+fn remove_styled_outline(mut world: DeferredWorld<'_>, context: HookContext) {
+    remove_styled(&mut world.commands().entity(context.entity));
+}
+
+fn remove_styled(entity: &mut EntityCommands) {
+    entity.try_remove::<(
+        StyledOutline<OutlineVolume>,
+        StyledOutline<OutlineStencil>,
+        StyledOutline<OutlineMode>,
+        StyledOutline<OutlineFace>,
+        StyledOutline<OutlinePlaneDepth>,
+        StyledOutline<OutlineAlphaMask>,
+    )>();
+}
+
+/// Copies the properties of each entity's style onto it when the handle or
+/// the style asset changes, or removes them while the style is not loaded.
+pub(crate) fn apply_outline_style(
+    mut commands: Commands,
+    query: Query<
+        (Entity, &OutlineStyleHandle),
+        Or<(
+            Changed<OutlineStyleHandle>,
+            AssetChanged<OutlineStyleHandle>,
+        )>,
+    >,
+    styles: Res<Assets<OutlineStyle>>,
+) {
+    for (entity, handle) in query.iter() {
+        let mut entity = commands.entity(entity);
+        let Some(style) = styles.get(&handle.0) else {
+            remove_styled(&mut entity);
+            continue;
+        };
+        set_styled(&mut entity, &style.volume);
+        set_styled(&mut entity, &style.stencil);
+        set_styled(&mut entity, &style.mode);
+        set_styled(&mut entity, &style.face);
+        set_styled(&mut entity, &style.plane_depth);
+        set_styled(&mut entity, &style.alpha_mask);
+    }
+}
+
+fn set_styled<T: Clone + Send + Sync + 'static>(entity: &mut EntityCommands, value: &Option<T>) {
+    match value {
+        Some(value) => {
+            entity.try_insert(StyledOutline(value.clone()));
+        }
+        None => {
+            entity.try_remove::<StyledOutline<T>>();
+        }
+    }
+}
+
+/// The representation of an [`OutlineStyle`] in a file.
+#[cfg(feature = "style_loader")]
+#[derive(serde::Deserialize)]
+struct OutlineStyleFile {
+    volume: Option<OutlineVolume>,
+    stencil: Option<OutlineStencil>,
+    mode: Option<OutlineMode>,
+    face: Option<OutlineFace>,
+    plane_depth: Option<OutlinePlaneDepth>,
+    alpha_mask: Option<OutlineAlphaMaskFile>,
+}
+
+/// The representation of an [`OutlineAlphaMask`] in a file, with the texture
+/// given as an asset path.
+#[cfg(feature = "style_loader")]
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct OutlineAlphaMaskFile {
+    texture: Option<String>,
+    channel: TextureChannel,
+    threshold: f32,
+}
+
+#[cfg(feature = "style_loader")]
+impl OutlineStyleFile {
+    fn into_style(self, load_context: &mut LoadContext<'_>) -> OutlineStyle {
+        OutlineStyle {
+            volume: self.volume,
+            stencil: self.stencil,
+            mode: self.mode,
+            face: self.face,
+            plane_depth: self.plane_depth,
+            alpha_mask: self.alpha_mask.map(|mask| OutlineAlphaMask {
+                texture: mask.texture.map(|path| load_context.load(path)),
+                channel: mask.channel,
+                threshold: mask.threshold,
+            }),
+        }
+    }
+}
+
+/// Failed to load an [`OutlineStyle`].
+#[cfg(feature = "style_loader")]
+#[derive(thiserror::Error, Debug)]
+pub enum OutlineStyleLoaderError {
+    /// The file could not be read.
+    #[error("could not read outline style: {0}")]
+    Io(#[from] std::io::Error),
+    /// The RON file could not be parsed as an outline style.
+    #[error("could not parse RON outline style: {0}")]
+    Ron(#[from] ron::de::SpannedError),
+    /// The JSON file could not be parsed as an outline style.
+    #[error("could not parse JSON outline style: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Loads [`OutlineStyle`] assets from RON files.
+#[cfg(feature = "style_loader")]
+#[derive(Default, TypePath)]
+pub(crate) struct RonOutlineStyleLoader;
+
+#[cfg(feature = "style_loader")]
+impl AssetLoader for RonOutlineStyleLoader {
+    type Asset = OutlineStyle;
+    type Settings = ();
+    type Error = OutlineStyleLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<OutlineStyle, OutlineStyleLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: OutlineStyleFile = ron::de::from_bytes(&bytes)?;
+        Ok(file.into_style(load_context))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["outline.ron"]
+    }
+}
+
+/// Loads [`OutlineStyle`] assets from JSON files.
+#[cfg(feature = "style_loader")]
+#[derive(Default, TypePath)]
+pub(crate) struct JsonOutlineStyleLoader;
+
+#[cfg(feature = "style_loader")]
+impl AssetLoader for JsonOutlineStyleLoader {
+    type Asset = OutlineStyle;
+    type Settings = ();
+    type Error = OutlineStyleLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<OutlineStyle, OutlineStyleLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: OutlineStyleFile = serde_json::from_slice(&bytes)?;
+        Ok(file.into_style(load_context))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["outline.json"]
+    }
+}
+
+#[cfg(all(test, feature = "style_loader"))]
+mod tests {
+    use super::*;
+    use crate::OutlineStencilEnabled;
+
+    fn assert_full_style(file: OutlineStyleFile) {
+        let volume = file.volume.unwrap();
+        assert!(volume.visible);
+        assert_eq!(volume.width, 4.0);
+        assert_eq!(volume.colour, Color::srgba(1.0, 0.5, 0.0, 1.0));
+        assert_eq!(volume.intensity, 2.0);
+        let stencil = file.stencil.unwrap();
+        assert_eq!(stencil.enabled, OutlineStencilEnabled::Never);
+        assert_eq!(stencil.offset, 1.0);
+        assert!(matches!(file.mode, Some(OutlineMode::ExtrudeReal)));
+        assert!(matches!(file.face, Some(OutlineFace::DoubleSided)));
+        let plane_depth = file.plane_depth.unwrap();
+        assert_eq!(plane_depth.model_plane_origin, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(plane_depth.model_plane_offset, Vec3::ZERO);
+        let alpha_mask = file.alpha_mask.unwrap();
+        assert_eq!(alpha_mask.texture.as_deref(), Some("textures/mask.png"));
+        assert!(matches!(alpha_mask.channel, TextureChannel::R));
+        assert_eq!(alpha_mask.threshold, 0.25);
+    }
+
+    fn assert_partial_style(file: OutlineStyleFile) {
+        // Omitted fields of a component take their default values
+        let volume = file.volume.unwrap();
+        let default_volume = OutlineVolume::default();
+        assert!(volume.visible);
+        assert_eq!(volume.width, default_volume.width);
+        assert_eq!(volume.colour, default_volume.colour);
+        assert_eq!(volume.intensity, default_volume.intensity);
+        let alpha_mask = file.alpha_mask.unwrap();
+        assert!(alpha_mask.texture.is_none());
+        assert!(matches!(alpha_mask.channel, TextureChannel::A));
+        assert_eq!(alpha_mask.threshold, 0.5);
+
+        // Omitted components are left unset
+        assert!(file.stencil.is_none());
+        assert!(file.mode.is_none());
+        assert!(file.face.is_none());
+        assert!(file.plane_depth.is_none());
+    }
+
+    #[test]
+    fn test_ron_style() {
+        let file: OutlineStyleFile = ron::de::from_str(
+            r#"(
+                volume: Some((
+                    visible: true,
+                    width: 4.0,
+                    colour: Srgba((red: 1.0, green: 0.5, blue: 0.0, alpha: 1.0)),
+                    intensity: 2.0,
+                )),
+                stencil: Some((enabled: Never, offset: 1.0)),
+                mode: Some(ExtrudeReal),
+                face: Some(DoubleSided),
+                plane_depth: Some((model_plane_origin: (0.0, 1.0, 0.0))),
+                alpha_mask: Some((
+                    texture: Some("textures/mask.png"),
+                    channel: R,
+                    threshold: 0.25,
+                )),
+            )"#,
+        )
+        .unwrap();
+        assert_full_style(file);
+    }
+
+    #[test]
+    fn test_ron_style_defaults() {
+        let file: OutlineStyleFile = ron::de::from_str(
+            r#"(
+                volume: Some((visible: true)),
+                alpha_mask: Some((threshold: 0.5)),
+            )"#,
+        )
+        .unwrap();
+        assert_partial_style(file);
+    }
+
+    #[test]
+    fn test_json_style() {
+        let file: OutlineStyleFile = serde_json::from_str(
+            r#"{
+                "volume": {
+                    "visible": true,
+                    "width": 4.0,
+                    "colour": { "Srgba": { "red": 1.0, "green": 0.5, "blue": 0.0, "alpha": 1.0 } },
+                    "intensity": 2.0
+                },
+                "stencil": { "enabled": "Never", "offset": 1.0 },
+                "mode": "ExtrudeReal",
+                "face": "DoubleSided",
+                "plane_depth": { "model_plane_origin": [0.0, 1.0, 0.0] },
+                "alpha_mask": {
+                    "texture": "textures/mask.png",
+                    "channel": "R",
+                    "threshold": 0.25
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_full_style(file);
+    }
+
+    #[test]
+    fn test_json_style_defaults() {
+        let file: OutlineStyleFile = serde_json::from_str(
+            r#"{
+                "volume": { "visible": true },
+                "alpha_mask": { "threshold": 0.5 }
+            }"#,
+        )
+        .unwrap();
+        assert_partial_style(file);
+    }
+
+    #[test]
+    fn test_invalid_style() {
+        assert!(ron::de::from_str::<OutlineStyleFile>("(mode: Some(Sideways))").is_err());
+        assert!(serde_json::from_str::<OutlineStyleFile>(r#"{ "face": "Back" }"#).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use std::time::Duration;
+
+use bevy::ecs::{lifecycle::HookContext, world::DeferredWorld};
+use bevy::math::curve::EaseFunction;
+use bevy::prelude::*;
+
+use crate::{
+    lerp_stencil, lerp_volume, ComputedOutline, OutlineStencil, OutlineVolume, OutlineWarmUp,
+};
+
+/// A component for animating the outline of an entity towards a target.
+///
+/// The transition starts from the entity's own [`OutlineVolume`] and
+/// [`OutlineStencil`] components where present, and otherwise from its
+/// current computed outline, which includes any inherited or styled
+/// properties. It sets the targeted components on the entity as it
+/// progresses, starting in the same frame unless a warm-up is needed. Once
+/// complete, the component is removed and an [`OutlineTransitionFinished`]
+/// event is triggered for the entity.
+///
+/// If the transition crosses between pipeline specialisations, such as from
+/// an opaque to a translucent colour, an [`OutlineWarmUp`] is applied one
+/// frame before the transition begins and restored once it ends.
+#[derive(Clone, Component)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+#[component(on_remove = restore_warm_up)]
+pub struct OutlineTransition {
+    /// The volume to transition to, if any.
+    pub volume: Option<OutlineVolume>,
+    /// The stencil to transition to, if any.
+    pub stencil: Option<OutlineStencil>,
+    /// The duration of the transition.
+    pub duration: Duration,
+    /// The easing function applied to the progress of the transition.
+    pub ease: EaseFunction,
+    elapsed: Duration,
+    start: Option<(OutlineVolume, OutlineStencil)>,
+}
+
+impl OutlineTransition {
+    /// Creates a transition of the given duration with linear easing and no
+    /// targets.
+    pub fn new(duration: Duration) -> Self {
+        OutlineTransition {
+            volume: None,
+            stencil: None,
+            duration,
+            ease: EaseFunction::Linear,
+            elapsed: Duration::ZERO,
+            start: None,
+        }
+    }
+
+    /// Sets the volume to transition to.
+    pub fn with_volume(self, volume: OutlineVolume) -> Self {
+        OutlineTransition {
+            volume: Some(volume),
+            ..self
+        }
+    }
+
+    /// Sets the stencil to transition to.
+    pub fn with_stencil(self, stencil: OutlineStencil) -> Self {
+        OutlineTransition {
+            stencil: Some(stencil),
+            ..self
+        }
+    }
+
+    /// Sets the easing function of the transition.
+    pub fn with_ease(self, ease: EaseFunction) -> Self {
+        OutlineTransition { ease, ..self }
+    }
+
+    /// Returns the warm-up required to move between the start and target
+    /// outlines without waiting for a pipeline to be specialised.
+    fn warm_up(
+        &self,
+        start_volume: &OutlineVolume,
+        start_stencil: &OutlineStencil,
+    ) -> OutlineWarmUp {
+        let (transparency, volume_offsets) = match &self.volume {
+            Some(volume) => (
+                volume.colour.is_fully_opaque() != start_volume.colour.is_fully_opaque(),
+                (volume.width == 0.0) != (start_volume.width == 0.0),
+            ),
+            None => (false, false),
+        };
+        let stencil_offsets = self
+            .stencil
+            .as_ref()
+            .is_some_and(|stencil| (stencil.offset == 0.0) != (start_stencil.offset == 0.0));
+        OutlineWarmUp {
+            transparency,
+            vertex_offsets: volume_offsets || stencil_offsets,
+        }
+    }
+}
+
+/// An event triggered for an entity when its [`OutlineTransition`] completes.
+#[derive(Clone, Copy, Debug, EntityEvent)]
+pub struct OutlineTransitionFinished {
+    /// The entity whose transition completed.
+    pub entity: Entity,
+}
+
+/// The warm-up of an entity before it was replaced for a transition.
+#[derive(Component)]
+struct TransitionWarmUp(Option<OutlineWarmUp>);
+
+/// Restores the warm-up of an entity when its transition is removed.
+fn restore_warm_up(mut world: DeferredWorld<'_>, context: HookContext) {
+    let Some(saved) = world
+        .get::<TransitionWarmUp>(context.entity)
+        .map(|saved| saved.0.clone())
+    else {
+        return;
+    };
+    let mut commands = world.commands();
+    let mut entity = commands.entity(context.entity);
+    entity.try_remove::<TransitionWarmUp>();
+    match saved {
+        Some(warm_up) => {
+            entity.try_insert(warm_up);
+        }
+        None => {
+            entity.try_remove::<OutlineWarmUp>();
+        }
+    }
+}
+
+pub(crate) fn update_outline_transitions(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut OutlineTransition,
+        &ComputedOutline,
+        Option<&OutlineVolume>,
+        Option<&OutlineStencil>,
+        Option<&OutlineWarmUp>,
+        Has<TransitionWarmUp>,
+    )>,
+    time: Res<Time>,
+) {
+    for (entity, mut transition, computed, volume, stencil, warm_up, saved) in query.iter_mut() {
+        let (start_volume, start_stencil) = if let Some(start) = transition.start.clone() {
+            transition.elapsed += time.delta();
+            start
+        } else {
+            let ComputedOutline(Some(computed)) = computed else {
+                continue;
+            };
+            // The computed outline is hidden along with the entity, so prefer
+            // the entity's own components.
+            let start_volume = volume.cloned().unwrap_or_else(|| OutlineVolume {
+                visible: computed.volume.value.enabled,
+                width: computed.volume.value.offset,
+                colour: computed.volume.value.colour.into(),
+                intensity: computed.volume.value.intensity,
+            });
+            let start_stencil = stencil.cloned().unwrap_or_else(|| OutlineStencil {
+                enabled: computed.stencil.value.enabled,
+                offset: computed.stencil.value.offset,
+            });
+            transition.start = Some((start_volume.clone(), start_stencil.clone()));
+
+            // Warm up any new specialisations a frame before they are needed.
+            let needed = transition.warm_up(&start_volume, &start_stencil);
+            if needed.transparency || needed.vertex_offsets {
+                if !saved {
+                    commands
+                        .entity(entity)
+                        .insert(TransitionWarmUp(warm_up.cloned()));
+                }
+                let current = warm_up.cloned().unwrap_or_default();
+                commands.entity(entity).insert(OutlineWarmUp {
+                    transparency: current.transparency || needed.transparency,
+                    vertex_offsets: current.vertex_offsets || needed.vertex_offsets,
+                });
+                continue;
+            }
+            (start_volume, start_stencil)
+        };
+
+        let t = if transition.duration.is_zero() {
+            1.0
+        } else {
+            (transition.elapsed.as_secs_f32() / transition.duration.as_secs_f32()).min(1.0)
+        };
+        let s = transition.ease.sample_clamped(t);
+
+        let mut entity_commands = commands.entity(entity);
+        if let Some(volume) = &transition.volume {
+            entity_commands.insert(lerp_volume(&start_volume, volume, s));
+        }
+        if let Some(stencil) = &transition.stencil {
+            entity_commands.insert(lerp_stencil(&start_stencil, stencil, s));
+        }
+        if t >= 1.0 {
+            entity_commands.remove::<OutlineTransition>();
+            commands.trigger(OutlineTransitionFinished { entity });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computed::compute_outline;
+    use crate::GlobalOutlineMode;
+
+    #[derive(Resource, Default)]
+    struct FinishedTransitions(Vec<Entity>);
+
+    fn setup(volume: OutlineVolume) -> (App, Entity) {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<GlobalOutlineMode>()
+            .init_resource::<FinishedTransitions>()
+            .add_observer(
+                |finished: On<OutlineTransitionFinished>,
+                 mut finished_transitions: ResMut<FinishedTransitions>| {
+                    finished_transitions.0.push(finished.entity);
+                },
+            )
+            .add_systems(
+                Update,
+                (update_outline_transitions, compute_outline).chain(),
+            );
+        let entity = app
+            .world_mut()
+            .spawn((
+                volume,
+                ComputedOutline::default(),
+                InheritedVisibility::VISIBLE,
+                GlobalTransform::default(),
+            ))
+            .id();
+        app.update();
+        (app, entity)
+    }
+
+    #[test]
+    fn test_transition_starts_from_intensity() {
+        let volume = OutlineVolume {
+            visible: true,
+            width: 2.0,
+            colour: Color::linear_rgb(1.0, 0.5, 0.0),
+            intensity: 4.0,
+        };
+        let (mut app, entity) = setup(volume.clone());
+
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(OutlineTransition::new(Duration::from_secs(1)).with_volume(volume.clone()));
+        app.update();
+        let transition = app.world().get::<OutlineTransition>(entity).unwrap();
+        let (start_volume, _) = transition.start.as_ref().unwrap();
+        assert_eq!(start_volume.colour, volume.colour);
+        assert_eq!(start_volume.intensity, volume.intensity);
+
+        // With no time elapsed, the volume is unchanged rather than taking
+        // the intensity twice.
+        app.update();
+        let current = app.world().get::<OutlineVolume>(entity).unwrap();
+        assert_eq!(current.colour, volume.colour);
+        assert_eq!(current.intensity, volume.intensity);
+    }
+
+    #[test]
+    fn test_zero_duration_transition() {
+        let (mut app, entity) = setup(OutlineVolume {
+            visible: true,
+            width: 2.0,
+            colour: Color::WHITE,
+            ..default()
+        });
+
+        app.world_mut().entity_mut(entity).insert(
+            OutlineTransition::new(Duration::ZERO).with_volume(OutlineVolume {
+                visible: true,
+                width: 4.0,
+                colour: Color::BLACK,
+                ..default()
+            }),
+        );
+        app.update();
+
+        // No warm-up is needed, so the transition completes immediately.
+        assert_eq!(app.world().get::<OutlineVolume>(entity).unwrap().width, 4.0);
+        assert!(app.world().get::<OutlineTransition>(entity).is_none());
+        assert_eq!(app.world().resource::<FinishedTransitions>().0, [entity]);
+    }
+
+    #[test]
+    fn test_transition_restores_warm_up() {
+        let volume = OutlineVolume {
+            visible: true,
+            width: 2.0,
+            colour: Color::WHITE,
+            ..default()
+        };
+        let (mut app, entity) = setup(volume.clone());
+
+        // Moving to a translucent colour needs the transparent specialisation
+        // to be warmed up, in addition to the entity's own warm-up.
+        app.world_mut().entity_mut(entity).insert((
+            OutlineWarmUp::default().with_vertex_offsets(true),
+            OutlineTransition::new(Duration::ZERO).with_volume(OutlineVolume {
+                colour: Color::WHITE.with_alpha(0.5),
+                ..volume
+            }),
+        ));
+        app.update();
+        let warm_up = app.world().get::<OutlineWarmUp>(entity).unwrap();
+        assert!(warm_up.transparency);
+        assert!(warm_up.vertex_offsets);
+        assert!(app.world().get::<OutlineTransition>(entity).is_some());
+        assert!(app.world().resource::<FinishedTransitions>().0.is_empty());
+
+        app.update();
+        let warm_up = app.world().get::<OutlineWarmUp>(entity).unwrap();
+        assert!(!warm_up.transparency);
+        assert!(warm_up.vertex_offsets);
+        assert!(app.world().get::<OutlineTransition>(entity).is_none());
+        assert!(app.world().get::<TransitionWarmUp>(entity).is_none());
+        assert_eq!(app.world().resource::<FinishedTransitions>().0, [entity]);
+    }
+}
@@ -0,0 +1,178 @@
+use bevy::image::TextureAtlasLayout;
+use bevy::prelude::*;
+use bevy::ui::{
+    widget::{ImageNode, NodeImageMode},
+    ComputedNode, ComputedUiTargetCamera, UiGlobalTransform,
+};
+
+use crate::{
+    computed::{ComputedDepth, Source},
+    sprite::{
+        image_region, region_uv_rect, ComputedSpriteOutline, SpriteOutlineQuad,
+        SPRITE_ALPHA_THRESHOLD,
+    },
+    uniforms::DrawMode,
+    ComputedOutline,
+};
+
+/// The depth in normalised device coordinates at which the outline quads of
+/// UI nodes are placed.
+///
+/// Bevy uses a reversed depth buffer, where the near plane lies at a depth of
+/// one and the far plane at zero, so this places the quads just beyond the
+/// near plane. Being in front of almost all of the scene, like the UI itself,
+/// outlines of UI nodes are not hidden by the scene when drawn with
+/// [`OutlineSceneOcclusion`](crate::OutlineSceneOcclusion). The margin from
+/// the near plane itself keeps the quads from being clipped by it.
+const UI_OUTLINE_NDC_DEPTH: f32 = 0.9;
+
+/// Returns the transform from physical pixels in a camera's viewport, with
+/// the y-axis pointing down, to the plane in front of the camera where UI
+/// outlines are drawn.
+fn world_from_viewport_pixel(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Affine3A> {
+    let viewport_size = camera.physical_viewport_size()?.as_vec2();
+    if viewport_size.cmpeq(Vec2::ZERO).any() {
+        return None;
+    }
+    let to_world =
+        |ndc: Vec2| camera.ndc_to_world(camera_transform, ndc.extend(UI_OUTLINE_NDC_DEPTH));
+    let origin = to_world(Vec2::new(-1.0, 1.0))?;
+    let x_axis = (to_world(Vec2::new(1.0, 1.0))? - origin) / viewport_size.x;
+    let y_axis = (to_world(Vec2::new(-1.0, -1.0))? - origin) / viewport_size.y;
+    // Points towards the camera, so that the quad faces it once the y-axis
+    // is flipped to point up.
+    let z_axis = y_axis.cross(x_axis).normalize();
+    Some(Affine3A::from_cols(
+        x_axis.into(),
+        y_axis.into(),
+        z_axis.into(),
+        origin.into(),
+    ))
+}
+
+/// Derives the outline quad of each outlined UI node, and substitutes the
+/// image of an [`ImageNode`] as the alpha mask for outlines which do not
+/// specify one.
+///
+/// The quad is placed in world space just in front of the node's target
+/// camera, such that one unit in the node's local space covers one physical
+/// pixel. The local space of the node has its origin at the node's centre and
+/// its y-axis pointing up, as for a sprite, and the depth plane of its outline
+/// is always placed at the centre of the node.
+///
+/// As for sprites, UI nodes are always outlined using jump flood. The quad is
+/// always rectangular, so rounded corners given by the node's border radius
+/// are not reflected in its outline unless the silhouette is taken from its
+/// image.
+#[allow(clippy::type_complexity)]
+pub(crate) fn compute_ui_outline(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &ComputedNode,
+        &UiGlobalTransform,
+        &ComputedUiTargetCamera,
+        Option<Ref<ImageNode>>,
+        &mut ComputedOutline,
+        Option<&mut ComputedSpriteOutline>,
+    )>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    quad: Res<SpriteOutlineQuad>,
+    images: Res<Assets<Image>>,
+    atlas_layouts: Res<Assets<TextureAtlasLayout>>,
+) {
+    for (entity, node, ui_transform, target_camera, image_node, mut computed, sprite_outline) in
+        query.iter_mut()
+    {
+        // The silhouette can only be taken from images which are stretched
+        // to fill the node.
+        let image_node = image_node.filter(|image_node| {
+            matches!(
+                image_node.image_mode,
+                NodeImageMode::Auto | NodeImageMode::Stretch
+            )
+        });
+
+        if image_node.as_ref().is_some_and(|i| i.is_changed()) || computed.is_changed() {
+            if let ComputedOutline(Some(computed)) = computed.as_mut() {
+                computed.mode.value.draw_mode = DrawMode::JumpFlood;
+                if let Some(image_node) = &image_node {
+                    if computed.alpha_mask.value.texture.is_none() {
+                        computed.alpha_mask.value.texture = Some(image_node.image.clone());
+                        if computed.alpha_mask.source == Source::Default {
+                            computed.alpha_mask.value.threshold = SPRITE_ALPHA_THRESHOLD;
+                        }
+                    }
+                }
+            }
+        }
+
+        let size = node.size();
+        if size.cmpeq(Vec2::ZERO).any() {
+            continue;
+        }
+        let Some((camera, camera_transform)) = target_camera
+            .get()
+            .and_then(|camera| cameras.get(camera).ok())
+        else {
+            continue;
+        };
+        let Some(world_from_pixel) = world_from_viewport_pixel(camera, camera_transform) else {
+            continue;
+        };
+
+        // UI transforms map from node space, with its y-axis pointing down,
+        // to viewport pixels.
+        let pixel_from_node = Affine3A::from_mat3_translation(
+            Mat3::from_mat2(ui_transform.matrix2),
+            ui_transform.translation.extend(0.0),
+        ) * Affine3A::from_scale(Vec3::new(1.0, -1.0, 1.0));
+        let world_from_local = world_from_pixel * pixel_from_node;
+
+        let new_depth = ComputedDepth {
+            world_plane_origin: world_from_local.translation.into(),
+            world_plane_offset: Vec3::ZERO,
+        };
+        let depth_changed = computed.0.as_ref().is_some_and(|computed| {
+            computed.depth.value.world_plane_origin != new_depth.world_plane_origin
+                || computed.depth.value.world_plane_offset != new_depth.world_plane_offset
+        });
+        if depth_changed {
+            if let ComputedOutline(Some(computed)) = computed.as_mut() {
+                computed.depth.value = new_depth;
+            }
+        }
+
+        let uv_rect = match &image_node {
+            Some(image_node) => {
+                let image_size = images.get(&image_node.image).map(|image| image.size_f32());
+                let rect = image_region(
+                    image_node.texture_atlas.as_ref(),
+                    image_node.rect,
+                    &atlas_layouts,
+                );
+                region_uv_rect(rect, image_size, image_node.flip_x, image_node.flip_y)
+            }
+            None => Vec4::new(0.0, 0.0, 1.0, 1.0),
+        };
+
+        let new_sprite_outline = ComputedSpriteOutline {
+            mesh_id: quad.0.id(),
+            local_from_quad: Affine3A::from_scale(size.extend(1.0)),
+            world_from_local: Some(world_from_local),
+            uv_rect,
+            camera: target_camera.get(),
+        };
+        match sprite_outline {
+            Some(mut sprite_outline) => {
+                sprite_outline.set_if_neq(new_sprite_outline);
+            }
+            None => {
+                commands.entity(entity).insert(new_sprite_outline);
+            }
+        }
+    }
+}
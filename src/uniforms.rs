@@ -18,16 +18,68 @@ use bevy::{
 use crate::{
     pipeline::OutlinePipeline,
     pipeline_key::{ComputedOutlineKey, EntityPipelineKey},
-    ComputedOutline, OutlineWarmUp,
+    ComputedOutline, OutlineDistanceScaling, OutlineGradient, OutlineTextureMapping, OutlineWarmUp,
+    OutlineWidthUnit,
 };
 
+#[cfg(any(feature = "sprite", feature = "ui"))]
+use crate::sprite::ComputedSpriteOutline;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct ExtractedRing {
+    pub(crate) offset: f32,
+    pub(crate) colour: Vec4,
+}
+
+#[cfg_attr(not(feature = "flood"), allow(dead_code))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct ExtractedGlow {
+    pub(crate) inner: f32,
+    pub(crate) falloff: f32,
+    pub(crate) curve: u32,
+}
+
+#[cfg_attr(not(feature = "flood"), allow(dead_code))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct ExtractedDashes {
+    pub(crate) length: f32,
+    pub(crate) gap: f32,
+    pub(crate) speed: f32,
+}
+
+#[cfg_attr(not(feature = "flood"), allow(dead_code))]
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum ExtractedGradient {
+    Stops(Vec<(f32, Vec4)>),
+    Texture(AssetId<Image>),
+}
+
 #[derive(Clone)]
 pub struct ExtractedOutline {
     pub(crate) stencil: bool,
     pub(crate) volume: bool,
+    pub(crate) occluded: bool,
+    pub(crate) rings: Vec<ExtractedRing>,
+    #[cfg_attr(not(feature = "flood"), allow(dead_code))]
+    pub(crate) glow: Option<ExtractedGlow>,
+    #[cfg_attr(not(feature = "flood"), allow(dead_code))]
+    pub(crate) gradient: Option<ExtractedGradient>,
+    #[cfg_attr(not(feature = "flood"), allow(dead_code))]
+    pub(crate) dashes: Option<ExtractedDashes>,
+    #[cfg_attr(not(feature = "flood"), allow(dead_code))]
+    pub(crate) width_unit: OutlineWidthUnit,
+    #[cfg_attr(not(feature = "flood"), allow(dead_code))]
+    pub(crate) distance_scaling: OutlineDistanceScaling,
+    #[cfg_attr(not(feature = "flood"), allow(dead_code))]
+    pub(crate) group: Option<u32>,
     pub(crate) draw_mode: DrawMode,
+    pub(crate) depth_mode: DepthMode,
     pub(crate) mesh_id: AssetId<Mesh>,
     pub(crate) alpha_mask_id: Option<AssetId<Image>>,
+    pub(crate) fill_texture_id: Option<AssetId<Image>>,
+    pub(crate) material_id: Option<UntypedAssetId>,
+    #[cfg_attr(not(feature = "flood"), allow(dead_code))]
+    pub(crate) fill_mapping: OutlineTextureMapping,
     pub(crate) pipeline_key: EntityPipelineKey,
     pub(crate) automatic_batching: bool,
     pub(crate) instance_data: OutlineInstanceUniform,
@@ -39,20 +91,73 @@ pub(crate) struct RenderOutlineInstances {
     entity_map: MainEntityHashMap<ExtractedOutline>,
 }
 
+impl RenderOutlineInstances {
+    pub(crate) fn get_mut(&mut self, entity: &MainEntity) -> Option<&mut ExtractedOutline> {
+        self.entity_map.get_mut(entity)
+    }
+}
+
 #[derive(Clone, ShaderType)]
 pub(crate) struct OutlineInstanceUniform {
     pub world_from_local: [Vec4; 3],
     pub world_plane_origin: Vec3,
     pub world_plane_offset: Vec3,
     pub volume_colour: Vec4,
+    pub occluded_colour: Vec4,
+    pub distance_scaling: Vec4,
+    pub alpha_mask_uv_rect: Vec4,
+    pub fill_tile_size: Vec2,
     pub volume_offset: f32,
+    pub occluded_offset: f32,
     pub stencil_offset: f32,
     pub alpha_mask_threshold: f32,
+    pub width_unit: u32,
+    pub distance_fade: f32,
     pub first_vertex_index: u32,
     pub current_skin_index: u32,
     pub current_morph_index: u32,
 }
 
+/// A render world component identifying which additional ring of an outline
+/// a phase item draws.
+#[derive(Component, Clone, Copy)]
+pub(crate) struct OutlineRingIndex(pub(crate) usize);
+
+/// The render entities used to key the phase items of additional rings, as
+/// these share their main entity with the outline volume. The entity for
+/// each ring index is spawned when first needed and reused thereafter.
+#[derive(Resource, Default)]
+pub(crate) struct OutlineRingEntities(Vec<Entity>);
+
+impl OutlineRingEntities {
+    /// Returns the render entity of the ring with the given index.
+    pub(crate) fn get(&self, index: usize) -> Entity {
+        self.0[index]
+    }
+}
+
+impl ExtractedOutline {
+    /// Returns the instance data for a phase item, substituting the width and
+    /// colour of the ring if it draws one.
+    pub(crate) fn instance_data_for(&self, ring: Option<usize>) -> OutlineInstanceUniform {
+        let mut instance_data = self.instance_data.clone();
+        if let Some(ring) = ring.and_then(|index| self.rings.get(index)) {
+            instance_data.volume_offset = ring.offset;
+            instance_data.volume_colour = ring.colour;
+        }
+        instance_data
+    }
+
+    /// Returns whether a volume of the given colour must be blended, either
+    /// because it or the mesh's outline colours are translucent or because it
+    /// fades out with distance.
+    pub(crate) fn is_transparent(&self, colour: Vec4) -> bool {
+        colour.w < 1.0
+            || self.pipeline_key.vertex_alpha()
+            || self.instance_data.distance_fade < f32::MAX
+    }
+}
+
 impl OutlineInstanceUniform {
     pub(crate) fn prepare_instance(
         &self,
@@ -89,6 +194,91 @@ pub(crate) enum DrawMode {
     JumpFlood = 2,
 }
 
+/// Query data for the mesh drawn by an outline, being either a [`Mesh3d`], a
+/// [`Mesh2d`], or the quad standing in for a sprite.
+pub(crate) type OutlineMeshData = (
+    Option<&'static Mesh3d>,
+    Option<&'static Mesh2d>,
+    OutlineQuadData,
+);
+
+/// Query data for the quad drawn in place of a mesh when outlining a sprite or
+/// UI node.
+#[cfg(any(feature = "sprite", feature = "ui"))]
+pub(crate) type OutlineQuadData = Option<&'static ComputedSpriteOutline>;
+
+/// Query data for the quad drawn in place of a mesh, there being none without
+/// the `sprite` and `ui` features.
+#[cfg(not(any(feature = "sprite", feature = "ui")))]
+pub(crate) type OutlineQuadData = ();
+
+/// Query filter for changes to the mesh drawn by an outline.
+#[cfg(any(feature = "sprite", feature = "ui"))]
+pub(crate) type OutlineMeshChanged = Or<(
+    Changed<Mesh3d>,
+    AssetChanged<Mesh3d>,
+    Changed<Mesh2d>,
+    AssetChanged<Mesh2d>,
+    Changed<ComputedSpriteOutline>,
+)>;
+
+/// Query filter for changes to the mesh drawn by an outline.
+#[cfg(not(any(feature = "sprite", feature = "ui")))]
+pub(crate) type OutlineMeshChanged = Or<(
+    Changed<Mesh3d>,
+    AssetChanged<Mesh3d>,
+    Changed<Mesh2d>,
+    AssetChanged<Mesh2d>,
+)>;
+
+/// Returns the mesh drawn by an outline, in order of preference if the entity
+/// has more than one.
+#[cfg(any(feature = "sprite", feature = "ui"))]
+pub(crate) fn outline_mesh_id(
+    (mesh_3d, mesh_2d, sprite): (
+        Option<&Mesh3d>,
+        Option<&Mesh2d>,
+        Option<&ComputedSpriteOutline>,
+    ),
+) -> Option<AssetId<Mesh>> {
+    mesh_3d
+        .map(|mesh| mesh.id())
+        .or_else(|| mesh_2d.map(|mesh| mesh.id()))
+        .or_else(|| sprite.map(|sprite| sprite.mesh_id))
+}
+
+/// Returns the mesh drawn by an outline, in order of preference if the entity
+/// has more than one.
+#[cfg(not(any(feature = "sprite", feature = "ui")))]
+pub(crate) fn outline_mesh_id(
+    (mesh_3d, mesh_2d, ()): (Option<&Mesh3d>, Option<&Mesh2d>, ()),
+) -> Option<AssetId<Mesh>> {
+    mesh_3d
+        .map(|mesh| mesh.id())
+        .or_else(|| mesh_2d.map(|mesh| mesh.id()))
+}
+
+/// Returns the world transform of an outlined entity, being that of its
+/// outline quad for UI nodes.
+#[cfg(any(feature = "sprite", feature = "ui"))]
+pub(crate) fn outline_world_from_local(
+    transform: Option<&GlobalTransform>,
+    sprite: Option<&ComputedSpriteOutline>,
+) -> Option<Affine3A> {
+    sprite
+        .and_then(|sprite| sprite.world_from_local)
+        .or_else(|| transform.map(GlobalTransform::affine))
+}
+
+/// Returns the world transform of an outlined entity.
+#[cfg(not(any(feature = "sprite", feature = "ui")))]
+pub(crate) fn outline_world_from_local(
+    transform: Option<&GlobalTransform>,
+    (): (),
+) -> Option<Affine3A> {
+    transform.map(GlobalTransform::affine)
+}
+
 #[derive(Resource)]
 pub(crate) struct OutlineInstanceBindGroup {
     pub bind_group: BindGroup,
@@ -96,24 +286,43 @@ pub(crate) struct OutlineInstanceBindGroup {
 
 #[allow(clippy::type_complexity)]
 pub(crate) fn extract_outlines(
+    mut commands: Commands,
     mut render_outlines: ResMut<RenderOutlineInstances>,
+    mut ring_entities: ResMut<OutlineRingEntities>,
     outlines: Extract<
         Query<(
             Entity,
             &ComputedOutline,
             &ComputedOutlineKey,
-            &GlobalTransform,
-            &Mesh3d,
+            Option<&GlobalTransform>,
+            OutlineMeshData,
             Has<NoAutomaticBatching>,
         )>,
     >,
 ) {
     render_outlines.entity_map.clear();
 
-    for (entity, computed, key, transform, mesh, no_automatic_batching) in outlines.iter() {
+    for (entity, computed, key, transform, mesh_handles, no_automatic_batching) in outlines.iter() {
         let ComputedOutline(Some(computed)) = computed else {
             continue;
         };
+        let Some(mesh_id) = outline_mesh_id(mesh_handles) else {
+            continue;
+        };
+        let Some(world_from_entity) = outline_world_from_local(transform, mesh_handles.2) else {
+            continue;
+        };
+        // Sprites are drawn as a quad sized and textured to match
+        #[cfg(any(feature = "sprite", feature = "ui"))]
+        let (world_from_local, alpha_mask_uv_rect) = match mesh_handles {
+            (None, None, Some(sprite)) => {
+                (world_from_entity * sprite.local_from_quad, sprite.uv_rect)
+            }
+            _ => (world_from_entity, Vec4::new(0.0, 0.0, 1.0, 1.0)),
+        };
+        #[cfg(not(any(feature = "sprite", feature = "ui")))]
+        let (world_from_local, alpha_mask_uv_rect) =
+            (world_from_entity, Vec4::new(0.0, 0.0, 1.0, 1.0));
         let extracted_outline = ExtractedOutline {
             stencil: computed
                 .stencil
@@ -121,25 +330,84 @@ pub(crate) fn extract_outlines(
                 .enabled
                 .is_enabled(computed.volume.value.enabled),
             volume: computed.volume.value.enabled,
+            occluded: computed.volume.value.enabled && computed.occluded.value.enabled,
+            rings: computed
+                .rings
+                .value
+                .iter()
+                .filter(|ring| computed.volume.value.enabled && ring.enabled)
+                .map(|ring| ExtractedRing {
+                    offset: ring.offset,
+                    colour: ring.hdr_colour().to_vec4(),
+                })
+                .collect(),
+            glow: computed.has_glow().then(|| ExtractedGlow {
+                inner: computed.glow.value.inner_width,
+                falloff: computed.glow.value.falloff_width,
+                curve: computed.glow.value.curve as u32,
+            }),
+            gradient: computed
+                .has_gradient()
+                .then(|| match &computed.gradient.value {
+                    OutlineGradient::Stops(stops) => {
+                        let mut stops = stops
+                            .iter()
+                            .take(OutlineGradient::MAX_STOPS)
+                            .map(|stop| (stop.position, LinearRgba::from(stop.colour).to_vec4()))
+                            .collect::<Vec<_>>();
+                        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+                        ExtractedGradient::Stops(stops)
+                    }
+                    OutlineGradient::Texture(texture) => ExtractedGradient::Texture(texture.id()),
+                }),
+            dashes: computed.has_dashes().then(|| ExtractedDashes {
+                length: computed.dashes.value.dash_length,
+                gap: computed.dashes.value.gap_length,
+                speed: computed.dashes.value.speed,
+            }),
+            width_unit: computed.width_unit.value,
+            distance_scaling: computed.distance_scaling.value.clone(),
+            group: computed.group.value,
             draw_mode: computed.mode.value.draw_mode,
-            mesh_id: mesh.id(),
+            depth_mode: computed.mode.value.depth_mode,
+            mesh_id,
             alpha_mask_id: computed
                 .alpha_mask
                 .value
                 .texture
                 .as_ref()
                 .map(|texture| texture.id()),
+            fill_texture_id: computed
+                .fill_texture
+                .value
+                .texture
+                .as_ref()
+                .map(|texture| texture.id()),
+            material_id: None,
+            fill_mapping: computed.fill_texture.value.mapping,
             pipeline_key: key.0,
             automatic_batching: !no_automatic_batching
                 && computed.mode.value.draw_mode == DrawMode::Extrude,
             instance_data: OutlineInstanceUniform {
-                world_from_local: Affine3::from(transform.affine()).to_transpose(),
+                world_from_local: Affine3::from(world_from_local).to_transpose(),
                 world_plane_origin: computed.depth.value.world_plane_origin,
                 world_plane_offset: computed.depth.value.world_plane_offset,
                 stencil_offset: computed.stencil.value.offset,
-                volume_offset: computed.volume.value.offset,
-                volume_colour: computed.volume.value.colour.to_vec4(),
+                volume_offset: computed.volume_offset(),
+                volume_colour: computed.volume.value.hdr_colour().to_vec4(),
+                occluded_offset: computed.occluded.value.offset,
+                occluded_colour: computed.occluded.value.hdr_colour().to_vec4(),
                 alpha_mask_threshold: computed.alpha_mask.value.threshold,
+                width_unit: computed.width_unit.value as u32,
+                distance_scaling: Vec4::new(
+                    computed.distance_scaling.value.near,
+                    computed.distance_scaling.value.far,
+                    computed.distance_scaling.value.min_width,
+                    computed.distance_scaling.value.max_width,
+                ),
+                distance_fade: computed.distance_scaling.value.fade_out,
+                fill_tile_size: computed.fill_texture.value.tile_size,
+                alpha_mask_uv_rect,
                 first_vertex_index: 0,
                 current_skin_index: 0,
                 current_morph_index: 0,
@@ -150,6 +418,20 @@ pub(crate) fn extract_outlines(
             .entity_map
             .insert(entity.into(), extracted_outline);
     }
+
+    // Spawn render entities for any rings beyond those seen before
+    let ring_count = render_outlines
+        .entity_map
+        .values()
+        .map(|outline| outline.rings.len())
+        .max()
+        .unwrap_or(0);
+    while ring_entities.0.len() < ring_count {
+        let index = ring_entities.0.len();
+        ring_entities
+            .0
+            .push(commands.spawn(OutlineRingIndex(index)).id());
+    }
 }
 
 pub(crate) fn prepare_outline_instance_bind_group(
@@ -173,6 +455,26 @@ pub(crate) fn prepare_outline_instance_bind_group(
     };
 }
 
+#[derive(Resource)]
+pub(crate) struct OutlineMesh2dBindGroup {
+    pub bind_group: BindGroup,
+}
+
+pub(crate) fn init_mesh_2d_bind_group(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    outline_pipeline: Res<OutlinePipeline>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    commands.insert_resource(OutlineMesh2dBindGroup {
+        bind_group: render_device.create_bind_group(
+            "outline_mesh_2d_bind_group",
+            &pipeline_cache.get_bind_group_layout(&outline_pipeline.mesh_2d_bind_group_layout),
+            &[],
+        ),
+    });
+}
+
 #[derive(Resource)]
 pub(crate) struct AlphaMaskBindGroups {
     pub bind_groups: HashMap<AssetId<Image>, BindGroup>,
@@ -232,3 +534,63 @@ pub(crate) fn prepare_alpha_mask_bind_groups(
         }
     }
 }
+
+#[derive(Resource)]
+pub(crate) struct FillTextureBindGroups {
+    pub bind_groups: HashMap<AssetId<Image>, BindGroup>,
+    pub default_bind_group: BindGroup,
+}
+
+pub(crate) fn init_fill_texture_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    fallback_image: Res<FallbackImage>,
+    outline_pipeline: Res<OutlinePipeline>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    commands.insert_resource(FillTextureBindGroups {
+        bind_groups: HashMap::new(),
+        default_bind_group: render_device.create_bind_group(
+            "default_outline_fill_texture_bind_group",
+            &pipeline_cache.get_bind_group_layout(&outline_pipeline.fill_texture_bind_group_layout),
+            &BindGroupEntries::sequential((
+                &fallback_image.d2.texture_view,
+                &fallback_image.d2.sampler,
+            )),
+        ),
+    });
+}
+
+pub(crate) fn prepare_fill_texture_bind_groups(
+    mut fill_texture_bind_groups: ResMut<FillTextureBindGroups>,
+    render_device: Res<RenderDevice>,
+    outline_pipeline: Res<OutlinePipeline>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    render_outlines: Res<RenderOutlineInstances>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    fill_texture_bind_groups.bind_groups.clear();
+
+    // Collect all unique textures used by outline fill textures
+    for outline in render_outlines.entity_map.values() {
+        if let Some(texture_id) = outline.fill_texture_id {
+            if let Some(gpu_image) = gpu_images.get(texture_id) {
+                fill_texture_bind_groups
+                    .bind_groups
+                    .entry(texture_id)
+                    .or_insert_with(|| {
+                        render_device.create_bind_group(
+                            "outline_fill_texture_bind_group",
+                            &pipeline_cache.get_bind_group_layout(
+                                &outline_pipeline.fill_texture_bind_group_layout,
+                            ),
+                            &BindGroupEntries::sequential((
+                                &gpu_image.texture_view,
+                                &gpu_image.sampler,
+                            )),
+                        )
+                    });
+            }
+        }
+    }
+}
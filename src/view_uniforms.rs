@@ -3,14 +3,15 @@ use bevy::prelude::*;
 use bevy::render::batching::gpu_preprocessing::GpuPreprocessingMode;
 use bevy::render::extract_component::ComponentUniforms;
 use bevy::render::render_phase::{ViewBinnedRenderPhases, ViewSortedRenderPhases};
-use bevy::render::render_resource::{BindGroup, BindGroupEntry};
+use bevy::render::render_resource::{BindGroup, BindGroupEntry, BindingResource};
 use bevy::render::render_resource::{PipelineCache, ShaderType};
 use bevy::render::renderer::RenderDevice;
 use bevy::render::sync_world::RenderEntity;
-use bevy::render::view::RetainedViewEntity;
+use bevy::render::view::{RetainedViewEntity, ViewDepthTexture};
 use bevy::render::Extract;
 
-use crate::node::{OpaqueOutline, StencilOutline, TransparentOutline};
+use crate::node::{OccludedOutline, OpaqueOutline, StencilOutline, TransparentOutline};
+use crate::occlusion::ResolvedSceneDepth;
 use crate::pipeline::OutlinePipeline;
 
 #[derive(Clone, Component, ShaderType)]
@@ -21,6 +22,9 @@ pub(crate) struct OutlineViewUniform {
     pub aspect: f32,
     pub scale_clip_from_logical: Vec2,
     pub scale_physical_from_logical: f32,
+    pub scale_logical_from_world: f32,
+    pub world_position: Vec3,
+    pub time: f32,
 }
 
 #[derive(Resource)]
@@ -28,6 +32,12 @@ pub(crate) struct OutlineViewBindGroup {
     pub(crate) bind_group: BindGroup,
 }
 
+/// View bind group for views which also sample the scene depth texture.
+#[derive(Component)]
+pub(crate) struct OutlineViewSceneDepthBindGroup {
+    pub(crate) bind_group: BindGroup,
+}
+
 #[derive(Component, Default)]
 pub(crate) struct OutlineQueueStatus {
     pub(crate) has_volume: bool,
@@ -39,7 +49,14 @@ pub(crate) fn extract_outline_view_uniforms(
     mut stencil_phases: ResMut<ViewBinnedRenderPhases<StencilOutline>>,
     mut opaque_phases: ResMut<ViewBinnedRenderPhases<OpaqueOutline>>,
     mut transparent_phases: ResMut<ViewSortedRenderPhases<TransparentOutline>>,
-    query: Extract<Query<(Entity, &RenderEntity, &Camera, &GlobalTransform), With<Camera3d>>>,
+    mut occluded_phases: ResMut<ViewSortedRenderPhases<OccludedOutline>>,
+    query: Extract<
+        Query<
+            (Entity, &RenderEntity, &Camera, &GlobalTransform),
+            Or<(With<Camera3d>, With<Camera2d>)>,
+        >,
+    >,
+    time: Extract<Res<Time>>,
 ) {
     fn transpose_3x3(m: &Affine3A) -> ([Vec4; 2], f32) {
         let transpose_3x3 = m.matrix3.transpose();
@@ -68,6 +85,9 @@ pub(crate) fn extract_outline_view_uniforms(
                     aspect: size.x / size.y,
                     scale_clip_from_logical: 2.0 / size,
                     scale_physical_from_logical: camera.target_scaling_factor().unwrap_or(1.0),
+                    scale_logical_from_world: scale_logical_from_world(camera, size),
+                    world_position: transform.translation(),
+                    time: time.elapsed_secs_wrapped(),
                 })
                 .insert(OutlineQueueStatus::default());
 
@@ -75,16 +95,27 @@ pub(crate) fn extract_outline_view_uniforms(
             stencil_phases.prepare_for_new_frame(retained_view_entity, GpuPreprocessingMode::None);
             opaque_phases.prepare_for_new_frame(retained_view_entity, GpuPreprocessingMode::None);
             transparent_phases.prepare_for_new_frame(retained_view_entity);
+            occluded_phases.prepare_for_new_frame(retained_view_entity);
         }
     }
 }
 
+/// Returns the size in logical pixels of one world unit at a clip-space w of
+/// one, for converting outline widths in world units.
+pub(crate) fn scale_logical_from_world(camera: &Camera, logical_size: Vec2) -> f32 {
+    0.5 * camera.clip_from_view().y_axis.y * logical_size.y
+}
+
 pub(crate) fn prepare_outline_view_bind_group(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
     outline_pipeline: Res<OutlinePipeline>,
     pipeline_cache: Res<PipelineCache>,
     view_uniforms: Res<ComponentUniforms<OutlineViewUniform>>,
+    views: Query<
+        (Entity, Option<&ResolvedSceneDepth>, &ViewDepthTexture),
+        With<OutlineViewUniform>,
+    >,
 ) {
     if let Some(view_binding) = view_uniforms.binding() {
         let bind_group = render_device.create_bind_group(
@@ -96,5 +127,32 @@ pub(crate) fn prepare_outline_view_bind_group(
             }],
         );
         commands.insert_resource(OutlineViewBindGroup { bind_group });
+
+        for (entity, scene_depth, depth) in views.iter() {
+            let Some(scene_depth) = scene_depth else {
+                commands
+                    .entity(entity)
+                    .remove::<OutlineViewSceneDepthBindGroup>();
+                continue;
+            };
+            let layout = outline_pipeline.view_layout(true, **scene_depth != Msaa::Off);
+            let bind_group = render_device.create_bind_group(
+                "outline_view_scene_depth_bind_group",
+                &pipeline_cache.get_bind_group_layout(layout),
+                &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: view_binding.clone(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(depth.view()),
+                    },
+                ],
+            );
+            commands
+                .entity(entity)
+                .insert(OutlineViewSceneDepthBindGroup { bind_group });
+        }
     }
 }